@@ -0,0 +1,320 @@
+//! Load generator: N bot connections spread over M rooms, reporting snapshot
+//! rate, ping latency percentiles and dropped/late ticks. Server-side tick
+//! durations for the load rooms are read from the server's `/metrics`.
+//!
+//! ```text
+//! snake-load --server 127.0.0.1:8080 --clients 200 --rooms 20 --duration 30
+//! ```
+
+use snake_bot::{
+    BotClient, BotTier, ConnectOptions, ModeKind, Rejoin, Strategy, TierStrategy, Update,
+};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+struct Args {
+    server: String,
+    clients: usize,
+    rooms: usize,
+    duration: Duration,
+    mode: Option<ModeKind>,
+    tier: BotTier,
+    /// Expected server tick rate, for spotting late snapshots.
+    hz: u32,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        server: "127.0.0.1:8080".into(),
+        clients: 50,
+        rooms: 5,
+        duration: Duration::from_secs(10),
+        mode: None,
+        tier: BotTier::Greedy,
+        hz: 10,
+    };
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().ok_or(format!("missing value for {arg}"));
+        let bad = |v: &str| format!("invalid value {v} for {arg}");
+        match arg.as_str() {
+            "--server" | "-s" => args.server = value()?,
+            "--clients" | "-n" => {
+                let v = value()?;
+                args.clients = v.parse().map_err(|_| bad(&v))?;
+            }
+            "--rooms" | "-m" => {
+                let v = value()?;
+                args.rooms = v.parse::<usize>().map_err(|_| bad(&v))?.max(1);
+            }
+            "--duration" | "-d" => {
+                let v = value()?;
+                args.duration = Duration::from_secs(v.parse().map_err(|_| bad(&v))?);
+            }
+            "--mode" => {
+                let v = value()?;
+                args.mode = Some(
+                    serde_json::from_value(serde_json::Value::String(v.clone()))
+                        .map_err(|_| bad(&v))?,
+                );
+            }
+            "--tier" | "-t" => {
+                let v = value()?;
+                args.tier = serde_json::from_value(serde_json::Value::String(v.clone()))
+                    .map_err(|_| bad(&v))?;
+            }
+            "--hz" => {
+                let v = value()?;
+                args.hz = v.parse::<u32>().map_err(|_| bad(&v))?.max(1);
+            }
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    Ok(args)
+}
+
+#[derive(Default)]
+struct Stats {
+    connected: usize,
+    failed: usize,
+    disconnected: usize,
+    snapshots: u64,
+    /// Ticks we never saw, from gaps in `seq`.
+    dropped: u64,
+    /// Snapshots arriving more than 1.5 tick periods after the previous one.
+    late: u64,
+    inputs: u64,
+    rtt_us: Vec<u64>,
+}
+
+impl Stats {
+    fn merge(&mut self, other: Stats) {
+        self.connected += other.connected;
+        self.failed += other.failed;
+        self.disconnected += other.disconnected;
+        self.snapshots += other.snapshots;
+        self.dropped += other.dropped;
+        self.late += other.late;
+        self.inputs += other.inputs;
+        self.rtt_us.extend(other.rtt_us);
+    }
+}
+
+async fn run_client(i: usize, args: Arc<Args>, epoch: Instant, deadline: Instant) -> Stats {
+    let mut stats = Stats::default();
+    let opts = ConnectOptions {
+        server: args.server.clone(),
+        name: format!("load-{i}"),
+        room: format!("load-{}", i % args.rooms),
+        mode: args.mode,
+    };
+    let mut client = match BotClient::connect_with(opts).await {
+        Ok(client) => client,
+        Err(_) => {
+            stats.failed = 1;
+            return stats;
+        }
+    };
+    stats.connected = 1;
+    client.start();
+
+    let mut strategy = TierStrategy::seeded(args.tier, i as u64);
+    let mut rejoin = Rejoin::default();
+    let period = Duration::from_secs(1) / args.hz;
+    let mut ping = tokio::time::interval(Duration::from_secs(1));
+    let mut last: Option<(u64, Instant)> = None;
+    let stop = tokio::time::sleep_until(deadline.into());
+    tokio::pin!(stop);
+
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            _ = ping.tick() => client.ping(epoch.elapsed().as_micros() as u64),
+            update = client.next_update() => match update {
+                None | Some(Update::Disconnected { .. }) => {
+                    stats.disconnected = 1;
+                    break;
+                }
+                Some(Update::Pong { t }) => {
+                    stats.rtt_us.push((epoch.elapsed().as_micros() as u64).saturating_sub(t));
+                }
+                Some(Update::State(world)) => {
+                    let now = Instant::now();
+                    if let Some((seq, at)) = last {
+                        if world.seq > seq {
+                            stats.snapshots += 1;
+                            stats.dropped += world.seq - seq - 1;
+                            if now - at > period * 3 / 2 {
+                                stats.late += 1;
+                            }
+                        }
+                    }
+                    if last.is_none_or(|(seq, _)| world.seq > seq) {
+                        last = Some((world.seq, now));
+                    }
+                    if !world.started || world.round_over {
+                        continue;
+                    }
+                    match world.my_snake() {
+                        Some(me) if me.alive => {
+                            client.send_dir(strategy.next_dir(&world));
+                            stats.inputs += 1;
+                        }
+                        Some(_) => {
+                            if let Some(cmd) = rejoin.on_state(&world) {
+                                client.send(cmd);
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                Some(Update::RoundResult { .. }) => client.restart(),
+            },
+        }
+    }
+    stats
+}
+
+/// Tick durations summed over every `load-*` room's histogram.
+#[derive(Default)]
+struct ServerTicks {
+    /// Cumulative count per bucket upper bound, in seconds.
+    buckets: BTreeMap<u64, (f64, u64)>,
+    sum: f64,
+    count: u64,
+}
+
+impl ServerTicks {
+    /// Smallest bucket bound holding at least `p` of the ticks, in ms.
+    fn quantile(&self, p: f64) -> Option<f64> {
+        let want = (self.count as f64 * p).ceil() as u64;
+        self.buckets
+            .values()
+            .find(|(_, n)| *n >= want)
+            .map(|(le, _)| le * 1000.0)
+    }
+}
+
+async fn scrape_ticks(server: &str) -> Option<ServerTicks> {
+    let host = server.trim_start_matches("ws://").split('/').next()?;
+    let mut stream = tokio::net::TcpStream::connect(host).await.ok()?;
+    let request = format!("GET /metrics HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.ok()?;
+    let (head, body) = response.split_once("\r\n\r\n")?;
+    if !head.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+
+    let mut ticks = ServerTicks::default();
+    let prefix = "snake_room_tick_duration_seconds";
+    for line in body.lines().filter(|l| l.starts_with(prefix)) {
+        let (series, value) = line.rsplit_once(' ')?;
+        if !series.contains("room=\"load-") {
+            continue;
+        }
+        let value: f64 = value.parse().ok()?;
+        if let Some(le) = series.split("le=\"").nth(1) {
+            let le = le.trim_end_matches("\"}");
+            let le: f64 = if le == "+Inf" {
+                f64::INFINITY
+            } else {
+                le.parse().ok()?
+            };
+            let entry = ticks.buckets.entry(le.to_bits()).or_insert((le, 0));
+            entry.1 += value as u64;
+        } else if series.starts_with(&format!("{prefix}_sum")) {
+            ticks.sum += value;
+        } else if series.starts_with(&format!("{prefix}_count")) {
+            ticks.count += value as u64;
+        }
+    }
+    Some(ticks)
+}
+
+fn percentile(sorted: &[u64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let i = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[i] as f64 / 1000.0
+}
+
+#[tokio::main]
+async fn main() -> Result<(), snake_bot::Error> {
+    let args = Arc::new(parse_args()?);
+    eprintln!(
+        "{} clients over {} rooms against {} for {:?}",
+        args.clients, args.rooms, args.server, args.duration
+    );
+
+    let epoch = Instant::now();
+    let deadline = epoch + args.duration;
+    let total = Arc::new(Mutex::new(Stats::default()));
+    let scrape = {
+        let server = args.server.clone();
+        // just before the clients hang up and their rooms close
+        let at = deadline - (args.duration / 10).min(Duration::from_millis(500));
+        tokio::spawn(async move {
+            tokio::time::sleep_until(at.into()).await;
+            scrape_ticks(&server).await
+        })
+    };
+    let tasks: Vec<_> = (0..args.clients)
+        .map(|i| {
+            let (args, total) = (args.clone(), total.clone());
+            tokio::spawn(async move {
+                let stats = run_client(i, args, epoch, deadline).await;
+                total.lock().unwrap().merge(stats);
+            })
+        })
+        .collect();
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    let mut s = std::mem::take(&mut *total.lock().unwrap());
+    s.rtt_us.sort_unstable();
+    let secs = args.duration.as_secs_f64().max(f64::EPSILON);
+    let expected = secs * args.hz as f64 * s.connected as f64;
+
+    println!(
+        "connections   {} ok, {} failed, {} dropped early",
+        s.connected, s.failed, s.disconnected
+    );
+    println!(
+        "snapshots     {} total, {:.1}/s overall, {:.2}/s per client ({:.0}% of {} Hz)",
+        s.snapshots,
+        s.snapshots as f64 / secs,
+        s.snapshots as f64 / secs / s.connected.max(1) as f64,
+        100.0 * s.snapshots as f64 / expected.max(1.0),
+        args.hz
+    );
+    println!(
+        "ticks         {} dropped, {} late (>1.5 periods)",
+        s.dropped, s.late
+    );
+    println!("inputs sent   {}", s.inputs);
+    println!(
+        "ping rtt ms   p50 {:.2}  p90 {:.2}  p99 {:.2}  max {:.2}  (n={})",
+        percentile(&s.rtt_us, 0.50),
+        percentile(&s.rtt_us, 0.90),
+        percentile(&s.rtt_us, 0.99),
+        percentile(&s.rtt_us, 1.0),
+        s.rtt_us.len()
+    );
+    match scrape.await.ok().flatten() {
+        Some(t) if t.count > 0 => println!(
+            "server tick   mean {:.3} ms  p50 <= {:.3}  p99 <= {:.3}  (n={}, from /metrics)",
+            t.sum * 1000.0 / t.count as f64,
+            t.quantile(0.50).unwrap_or(f64::NAN),
+            t.quantile(0.99).unwrap_or(f64::NAN),
+            t.count
+        ),
+        _ => println!("server tick   not exposed by this server"),
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use crate::{
+    Dir, DisconnectReason, Error, ModeKind, RankEntry, Rejoin, Strategy, World, C2S,
+    PROTOCOL_VERSION, S2C,
+};
+use futures_util::{SinkExt, Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// Where to connect and which room to join.
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    /// `host:port`, with or without a `ws://` scheme.
+    pub server: String,
+    pub name: String,
+    pub room: String,
+    /// Rules to use if this connection creates the room.
+    pub mode: Option<ModeKind>,
+}
+
+impl ConnectOptions {
+    fn url(&self) -> Result<String, Error> {
+        let base = if self.server.contains("://") {
+            self.server.clone()
+        } else {
+            format!("ws://{}", self.server)
+        };
+        let mut url = url::Url::parse(&base)?;
+        if url.port().is_none() {
+            let _ = url.set_port(Some(8080));
+        }
+        url.set_path("/ws");
+        url.query_pairs_mut()
+            .clear()
+            .append_pair("room", &self.room)
+            .append_pair("name", &self.name)
+            .append_pair("v", &PROTOCOL_VERSION.to_string());
+        if let Some(mode) = self.mode {
+            let mode = serde_json::to_value(mode)?;
+            if let Some(mode) = mode.as_str() {
+                url.query_pairs_mut().append_pair("mode", mode);
+            }
+        }
+        Ok(url.to_string())
+    }
+}
+
+/// A typed message from the server, applied to the client's `World` first.
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// A new tick; the payload is the world after applying it.
+    State(Box<World>),
+    RoundResult {
+        winner: Option<String>,
+        standings: Vec<RankEntry>,
+    },
+    Pong {
+        t: u64,
+    },
+    /// The server is closing the connection; no more updates follow.
+    Disconnected {
+        reason: DisconnectReason,
+    },
+}
+
+/// Headless connection to a Snake Online server.
+pub struct BotClient {
+    world: World,
+    rx: mpsc::UnboundedReceiver<S2C>,
+    tx: mpsc::UnboundedSender<C2S>,
+}
+
+impl BotClient {
+    /// Connect to `server` and join `room` as `name`.
+    pub async fn connect(server: &str, name: &str, room: &str) -> Result<Self, Error> {
+        Self::connect_with(ConnectOptions {
+            server: server.to_string(),
+            name: name.to_string(),
+            room: room.to_string(),
+            mode: None,
+        })
+        .await
+    }
+
+    /// Connect and wait for the server's `Hello`.
+    pub async fn connect_with(opts: ConnectOptions) -> Result<Self, Error> {
+        let (tx, mut rx_cmd) = mpsc::unbounded_channel::<C2S>();
+        let (tx_state, mut rx) = mpsc::unbounded_channel::<S2C>();
+
+        let (ws_stream, _) = connect_async(opts.url()?).await?;
+        let (mut ws_tx, mut ws_rx) = ws_stream.split();
+
+        // writer task
+        tokio::spawn(async move {
+            while let Some(cmd) = rx_cmd.recv().await {
+                let Ok(txt) = serde_json::to_string(&cmd) else {
+                    continue;
+                };
+                if ws_tx.send(Message::Text(txt)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        // reader task
+        tokio::spawn(async move {
+            while let Some(Ok(msg)) = ws_rx.next().await {
+                if let Message::Text(txt) = msg {
+                    if let Ok(parsed) = serde_json::from_str::<S2C>(&txt) {
+                        if tx_state.send(parsed).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        let world = loop {
+            match rx.recv().await {
+                Some(S2C::Hello {
+                    player_id,
+                    grid,
+                    tick_hz,
+                    mode,
+                }) => break World::new(player_id, grid, mode, tick_hz),
+                Some(S2C::Disconnect { reason }) => {
+                    return Err(format!("refused by server: {reason:?}").into())
+                }
+                Some(_) => continue,
+                None => return Err("connection closed before hello".into()),
+            }
+        };
+        Ok(Self { world, rx, tx })
+    }
+
+    /// The world as of the last update received.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Wait for the next message from the server; `None` once the connection is gone.
+    pub async fn next_update(&mut self) -> Option<Update> {
+        loop {
+            let msg = self.rx.recv().await?;
+            if let Some(update) = self.apply(msg) {
+                return Some(update);
+            }
+        }
+    }
+
+    /// Updates as a stream, ending when the connection closes.
+    pub fn updates(&mut self) -> impl Stream<Item = Update> + '_ {
+        futures_util::stream::unfold(self, |client| async move {
+            let update = client.next_update().await?;
+            Some((update, client))
+        })
+    }
+
+    fn apply(&mut self, msg: S2C) -> Option<Update> {
+        match msg {
+            S2C::State {
+                seq,
+                started,
+                food,
+                players,
+                arena,
+                round_over,
+                winner,
+                time_left_ms,
+                teams,
+            } => {
+                let w = &mut self.world;
+                w.seq = seq;
+                w.started = started;
+                w.food = food;
+                w.players = players;
+                w.arena = arena;
+                w.round_over = round_over;
+                w.winner = winner;
+                w.time_left_ms = time_left_ms;
+                w.teams = teams;
+                Some(Update::State(Box::new(w.clone())))
+            }
+            S2C::RoundResult { winner, standings } => {
+                Some(Update::RoundResult { winner, standings })
+            }
+            S2C::Pong { t } => Some(Update::Pong { t }),
+            S2C::Disconnect { reason } => Some(Update::Disconnected { reason }),
+            S2C::Hello { .. } | S2C::Announcement { .. } | S2C::Error { .. } => None,
+        }
+    }
+
+    pub fn send(&self, cmd: C2S) {
+        let _ = self.tx.send(cmd);
+    }
+
+    /// Queue a turn for the next tick.
+    pub fn send_dir(&self, dir: Dir) {
+        self.send(C2S::Input { dir });
+    }
+
+    pub fn start(&self) {
+        self.send(C2S::Start);
+    }
+
+    pub fn respawn(&self) {
+        self.send(C2S::Respawn);
+    }
+
+    pub fn restart(&self) {
+        self.send(C2S::Restart);
+    }
+
+    pub fn ping(&self, t: u64) {
+        self.send(C2S::Ping { t });
+    }
+
+    /// Play with `strategy` until the connection closes: start the room, steer
+    /// every tick, come back after dying as `Rejoin` allows and rejoin the next
+    /// round when one ends.
+    pub async fn run<S: Strategy>(mut self, mut strategy: S) -> Result<(), Error> {
+        self.start();
+        let mut last_sent: Option<(u64, Dir)> = None;
+        let mut rejoin = Rejoin::default();
+        while let Some(update) = self.next_update().await {
+            match update {
+                Update::State(world) => {
+                    if !world.started || world.round_over {
+                        continue;
+                    }
+                    let Some(me) = world.my_snake() else {
+                        continue;
+                    };
+                    if !me.alive {
+                        if let Some(cmd) = rejoin.on_state(&world) {
+                            self.send(cmd);
+                        }
+                        continue;
+                    }
+                    let dir = strategy.next_dir(&world);
+                    // one input per tick, and only when it changes our course
+                    if Some(dir) != World::heading(me)
+                        && last_sent.is_none_or(|(seq, _)| seq != world.seq)
+                    {
+                        self.send_dir(dir);
+                        last_sent = Some((world.seq, dir));
+                    }
+                }
+                Update::RoundResult { standings, .. } => {
+                    strategy.on_round_end(&standings);
+                    self.restart();
+                }
+                Update::Pong { .. } => {}
+                Update::Disconnected { reason } => {
+                    return Err(format!("disconnected by server: {reason:?}").into())
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! Headless client for writing Snake Online bots.
+//!
+//! ```no_run
+//! use snake_bot::{BotClient, Dir, Strategy, World};
+//!
+//! struct AlwaysRight;
+//!
+//! impl Strategy for AlwaysRight {
+//!     fn next_dir(&mut self, _world: &World) -> Dir {
+//!         Dir::Right
+//!     }
+//! }
+//!
+//! # async fn demo() -> Result<(), snake_bot::Error> {
+//! let client = BotClient::connect("127.0.0.1:8080", "Righty", "lobby").await?;
+//! client.run(AlwaysRight).await
+//! # }
+//! ```
+
+mod client;
+mod rejoin;
+mod strategy;
+mod world;
+
+pub use client::{BotClient, ConnectOptions, Update};
+pub use rejoin::Rejoin;
+pub use strategy::{Strategy, TierStrategy};
+pub use world::World;
+
+pub use snake_server::bot::{BotTier, BotView};
+pub use snake_server::model::{
+    Arena, Cell, Dir, DisconnectReason, ModeKind, PlayerSnapshot, RankEntry, TeamScore, C2S,
+    PROTOCOL_VERSION, S2C,
+};
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -0,0 +1,48 @@
+use snake_bot::{BotClient, BotTier, ConnectOptions, ModeKind, TierStrategy};
+
+fn parse_args() -> Result<(ConnectOptions, BotTier), String> {
+    let mut opts = ConnectOptions {
+        server: "127.0.0.1:8080".to_string(),
+        name: "Bot".to_string(),
+        room: "lobby".to_string(),
+        mode: None,
+    };
+    let mut tier = BotTier::Greedy;
+
+    // Same small flag style as the GUI client: --server/-s, --name/-n, --room/-r,
+    // plus --mode/-m and --tier/-t
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--server" | "-s" => opts.server = value()?,
+            "--name" | "-n" => opts.name = value()?,
+            "--room" | "-r" => opts.room = value()?,
+            "--mode" | "-m" => {
+                let v = value()?;
+                opts.mode = Some(
+                    serde_json::from_value::<ModeKind>(serde_json::Value::String(v.clone()))
+                        .map_err(|_| format!("unknown mode {v}"))?,
+                );
+            }
+            "--tier" | "-t" => {
+                let v = value()?;
+                tier = serde_json::from_value(serde_json::Value::String(v.clone()))
+                    .map_err(|_| format!("unknown tier {v} (random, greedy, survivor)"))?;
+            }
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    Ok((opts, tier))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), snake_bot::Error> {
+    let (opts, tier) = parse_args()?;
+    eprintln!(
+        "connecting {} to {} room={} tier={tier:?}",
+        opts.name, opts.server, opts.room
+    );
+    let client = BotClient::connect_with(opts).await?;
+    client.run(TierStrategy::new(tier)).await
+}
@@ -0,0 +1,42 @@
+use crate::{ModeKind, World, C2S};
+
+/// When a dead snake asks to play again.
+///
+/// With lives left it respawns; out of lives in classic or team it restarts
+/// at once. Royale and time attack only restart between rounds, so there it
+/// spectates until `RoundResult`, which callers answer with one `Restart`.
+/// Each death is asked about once, with a retry every couple of seconds in
+/// case the request raced a tick, which keeps well inside the server's
+/// `Respawn`/`Restart` rate limits.
+#[derive(Debug, Clone, Default)]
+pub struct Rejoin {
+    /// Seq of the snapshot we last asked on, while still dead.
+    asked_at: Option<u64>,
+}
+
+impl Rejoin {
+    /// What to send for this snapshot, if anything.
+    pub fn on_state(&mut self, world: &World) -> Option<C2S> {
+        let me = world.my_snake()?;
+        if me.alive {
+            self.asked_at = None;
+            return None;
+        }
+        if !world.started || world.round_over {
+            return None;
+        }
+        let retry = u64::from(world.tick_hz.max(1)) * 2;
+        if self.asked_at.is_some_and(|seq| world.seq < seq + retry) {
+            return None;
+        }
+        let cmd = if me.lives > 0 {
+            C2S::Respawn
+        } else if matches!(world.mode, ModeKind::Classic | ModeKind::Team) {
+            C2S::Restart
+        } else {
+            return None;
+        };
+        self.asked_at = Some(world.seq);
+        Some(cmd)
+    }
+}
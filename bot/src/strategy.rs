@@ -0,0 +1,42 @@
+use crate::{BotTier, Dir, RankEntry, World};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Decides a move each tick. Only called while our snake is alive.
+pub trait Strategy: Send {
+    fn next_dir(&mut self, world: &World) -> Dir;
+
+    /// Called with the final standings whenever a round ends.
+    fn on_round_end(&mut self, _standings: &[RankEntry]) {}
+}
+
+/// One of the server's built-in bot tiers, run client-side.
+pub struct TierStrategy {
+    tier: BotTier,
+    rng: StdRng,
+}
+
+impl TierStrategy {
+    pub fn new(tier: BotTier) -> Self {
+        Self {
+            tier,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Deterministic variant for reproducible runs.
+    pub fn seeded(tier: BotTier, seed: u64) -> Self {
+        Self {
+            tier,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for TierStrategy {
+    fn next_dir(&mut self, world: &World) -> Dir {
+        match world.bot_view() {
+            Some(view) => snake_server::bot::choose(self.tier, &view, &mut self.rng),
+            None => Dir::Right,
+        }
+    }
+}
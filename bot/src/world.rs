@@ -0,0 +1,81 @@
+use crate::{Arena, BotView, Cell, Dir, ModeKind, PlayerSnapshot, TeamScore};
+
+/// Read-only view of the room as of the latest `State` message.
+#[derive(Debug, Clone)]
+pub struct World {
+    /// Our own player id.
+    pub me: String,
+    pub grid: (i32, i32),
+    pub mode: ModeKind,
+    pub tick_hz: u32,
+    pub seq: u64,
+    pub started: bool,
+    pub food: Cell,
+    pub arena: Arena,
+    pub players: Vec<PlayerSnapshot>,
+    pub round_over: bool,
+    pub winner: Option<String>,
+    pub time_left_ms: Option<u64>,
+    pub teams: Vec<TeamScore>,
+}
+
+impl World {
+    pub(crate) fn new(me: String, grid: (i32, i32), mode: ModeKind, tick_hz: u32) -> Self {
+        Self {
+            me,
+            grid,
+            mode,
+            tick_hz,
+            seq: 0,
+            started: false,
+            food: Cell(0, 0),
+            arena: Arena::full(grid.0, grid.1),
+            players: vec![],
+            round_over: false,
+            winner: None,
+            time_left_ms: None,
+            teams: vec![],
+        }
+    }
+
+    /// Our own snake, once the server has placed it.
+    pub fn my_snake(&self) -> Option<&PlayerSnapshot> {
+        self.players.iter().find(|p| p.id == self.me)
+    }
+
+    /// Every other live snake.
+    pub fn enemies(&self) -> impl Iterator<Item = &PlayerSnapshot> {
+        self.players.iter().filter(|p| p.alive && p.id != self.me)
+    }
+
+    /// Direction a snake is heading, read off its head and neck.
+    pub fn heading(p: &PlayerSnapshot) -> Option<Dir> {
+        match p.body.as_slice() {
+            [head, neck, ..] => match (head.0 - neck.0, head.1 - neck.1) {
+                (0, -1) => Some(Dir::Up),
+                (0, 1) => Some(Dir::Down),
+                (-1, 0) => Some(Dir::Left),
+                (1, 0) => Some(Dir::Right),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// True if `c` is inside the arena and no live snake is on it.
+    pub fn is_free(&self, c: Cell) -> bool {
+        self.arena.contains(c) && !self.players.iter().any(|p| p.alive && p.body.contains(&c))
+    }
+
+    /// The same view the server hands its own bots, for reusing `snake_server::bot::choose`.
+    pub fn bot_view(&self) -> Option<BotView<'_>> {
+        let me = self.my_snake().filter(|p| p.alive)?;
+        Some(BotView {
+            arena: self.arena,
+            food: self.food,
+            me: &me.body,
+            dir: Self::heading(me).unwrap_or(Dir::Right),
+            others: self.enemies().map(|p| p.body.as_slice()).collect(),
+        })
+    }
+}
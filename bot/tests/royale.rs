@@ -0,0 +1,66 @@
+//! The SDK's `BotClient::run` against a real server.
+
+use snake_bot::{BotClient, BotTier, ConnectOptions, Dir, ModeKind, Strategy, TierStrategy, World};
+use snake_server::ServerBuilder;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+/// Drives straight into the nearest wall.
+struct Doomed;
+
+impl Strategy for Doomed {
+    fn next_dir(&mut self, world: &World) -> Dir {
+        World::heading(world.my_snake().expect("alive")).unwrap_or(Dir::Up)
+    }
+}
+
+async fn join(server: &str, name: &str) -> BotClient {
+    BotClient::connect_with(ConnectOptions {
+        server: server.to_string(),
+        name: name.to_string(),
+        room: "royale".into(),
+        mode: Some(ModeKind::BattleRoyale),
+    })
+    .await
+    .expect("bot should connect")
+}
+
+#[tokio::test]
+async fn eliminated_bot_spectates_instead_of_flooding() {
+    let server = ServerBuilder::new()
+        .manual_ticks()
+        .bind(Ipv4Addr::LOCALHOST.into())
+        .port(0)
+        .start()
+        .await
+        .expect("server should start");
+    let addr = server.local_addr().to_string();
+
+    let doomed = join(&addr, "doomed").await;
+    let doomed_id = doomed.world().me.clone();
+    let mut bots = vec![tokio::spawn(doomed.run(Doomed))];
+    for (i, name) in ["s1", "s2"].into_iter().enumerate() {
+        let client = join(&addr, name).await;
+        let strategy = TierStrategy::seeded(BotTier::Survivor, i as u64);
+        bots.push(tokio::spawn(client.run(strategy)));
+    }
+
+    // well past the wall, then long enough for a restart per snapshot to
+    // run through the restart budget several times over
+    let admin = server.admin();
+    let mut eliminated = false;
+    for _ in 0..200 {
+        server.tick();
+        tokio::time::sleep(Duration::from_millis(15)).await;
+        let room = admin.room("royale").expect("room exists");
+        eliminated |= room
+            .players
+            .iter()
+            .any(|p| p.id == doomed_id && !p.alive && p.lives == 0);
+        for bot in &bots {
+            assert!(!bot.is_finished(), "a bot's connection ended");
+        }
+    }
+    assert!(eliminated, "doomed bot never ran out of lives");
+    server.stop().await.unwrap();
+}
@@ -0,0 +1,165 @@
+//! Local AI that plays for the user, plus the menu's attract-mode demo.
+//!
+//! Both reuse the server's bot logic: the autopilot feeds it the `RemoteWorld`
+//! and steers through `NetClient::send_dir` like a keyboard would; the attract
+//! mode runs a bots-only room in-process with no server at all.
+
+use crate::net::{self, Dir, PlayerSnapshot};
+use crate::ui::RemoteWorld;
+use eframe::egui::{self, Color32, Rect};
+use snake_server::bot::{self, BotTier, BotView};
+use snake_server::env::{EnvConfig, SnakeEnv};
+use snake_server::model as sm;
+use std::time::{Duration, Instant};
+
+fn to_server(c: net::Cell) -> sm::Cell {
+    sm::Cell(c.0, c.1)
+}
+
+fn from_server(d: sm::Dir) -> Dir {
+    match d {
+        sm::Dir::Up => Dir::Up,
+        sm::Dir::Down => Dir::Down,
+        sm::Dir::Left => Dir::Left,
+        sm::Dir::Right => Dir::Right,
+    }
+}
+
+/// Direction a snake is moving, read off its head and neck.
+fn heading(body: &[sm::Cell]) -> sm::Dir {
+    match body {
+        [h, n, ..] => match (h.0 - n.0, h.1 - n.1) {
+            (0, -1) => sm::Dir::Up,
+            (0, 1) => sm::Dir::Down,
+            (-1, 0) => sm::Dir::Left,
+            _ => sm::Dir::Right,
+        },
+        _ => sm::Dir::Right,
+    }
+}
+
+pub struct Autopilot {
+    tier: BotTier,
+    last: Option<Dir>,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            tier: BotTier::Survivor,
+            last: None,
+        }
+    }
+}
+
+impl Autopilot {
+    /// Pick a move for `me`, or `None` when we're not on the board or already heading there.
+    pub fn steer(&mut self, world: &RemoteWorld, me: &str) -> Option<Dir> {
+        let body = |p: &PlayerSnapshot| p.body.iter().copied().map(to_server).collect::<Vec<_>>();
+        let mine = world.players.iter().find(|p| p.id == me && p.alive)?;
+        let me_body = body(mine);
+        let others: Vec<Vec<sm::Cell>> = world
+            .players
+            .iter()
+            .filter(|p| p.alive && p.id != me)
+            .map(body)
+            .collect();
+        let view = BotView {
+            arena: sm::Arena {
+                min: to_server(world.arena.min),
+                max: to_server(world.arena.max),
+            },
+            food: to_server(world.food),
+            me: &me_body,
+            dir: heading(&me_body),
+            others: others.iter().map(Vec::as_slice).collect(),
+        };
+        let current = from_server(view.dir);
+        let d = from_server(bot::choose(self.tier, &view, &mut rand::thread_rng()));
+        let changed = d != current && Some(d) != self.last;
+        self.last = Some(d);
+        changed.then_some(d)
+    }
+}
+
+/// Bots-only room simulated locally and painted behind the main menu.
+pub struct Attract {
+    env: SnakeEnv,
+    seed: u64,
+    last_step: Instant,
+}
+
+const ATTRACT_TICK: Duration = Duration::from_millis(100);
+/// Start a fresh board every so often so the demo doesn't silt up.
+const ATTRACT_TICKS: u64 = 1500;
+
+impl Default for Attract {
+    fn default() -> Self {
+        let env = SnakeEnv::new(EnvConfig {
+            grid_w: 32,
+            grid_h: 18,
+            agents: 0,
+            bots: vec![
+                BotTier::Survivor,
+                BotTier::Greedy,
+                BotTier::Greedy,
+                BotTier::RandomSafe,
+            ],
+            max_ticks: ATTRACT_TICKS,
+            ..Default::default()
+        });
+        Self {
+            env,
+            seed: 0,
+            last_step: Instant::now(),
+        }
+    }
+}
+
+impl Attract {
+    pub fn paint(&mut self, ctx: &egui::Context, painter: &egui::Painter, rect: Rect) {
+        if self.last_step.elapsed() >= ATTRACT_TICK {
+            self.last_step = Instant::now();
+            if self.env.step(&[]).info.truncated {
+                self.seed += 1;
+                self.env.reset(self.seed);
+            }
+        }
+        ctx.request_repaint_after(ATTRACT_TICK);
+
+        let sm::S2C::State { food, players, .. } = self.env.snapshot() else {
+            return;
+        };
+        let cell = (rect.width() / 32.0).min(rect.height() / 18.0);
+        let origin = rect.center() - egui::vec2(cell * 16.0, cell * 9.0);
+        let cell_rect = |c: sm::Cell| {
+            Rect::from_min_size(
+                origin + egui::vec2(c.0 as f32 * cell, c.1 as f32 * cell),
+                egui::vec2(cell, cell),
+            )
+            .shrink(cell * 0.08)
+        };
+        let tints = [
+            Color32::from_rgb(90, 200, 120),
+            Color32::from_rgb(90, 150, 230),
+            Color32::from_rgb(220, 200, 90),
+            Color32::from_rgb(200, 90, 200),
+        ];
+        painter.rect_filled(
+            cell_rect(food),
+            cell * 0.3,
+            Color32::from_rgba_unmultiplied(230, 70, 70, 70),
+        );
+        for (i, p) in players.iter().filter(|p| p.alive).enumerate() {
+            let [r, g, b, _] = tints[i % tints.len()].to_array();
+            for (j, c) in p.body.iter().enumerate() {
+                let alpha = if j == 0 { 70 } else { 40 };
+                painter.rect_filled(
+                    cell_rect(*c),
+                    cell * 0.2,
+                    Color32::from_rgba_unmultiplied(r, g, b, alpha),
+                );
+            }
+        }
+    }
+}
@@ -1,8 +1,12 @@
+use crate::net::ConnState;
 use crate::theme::ACCENT;
-use eframe::egui::{self, Align, Frame, Layout, RichText, TopBottomPanel};
+use eframe::egui::{self, Align, Color32, Frame, Layout, RichText, TopBottomPanel};
 
 #[derive(Default)]
-pub struct BottomBar;
+pub struct BottomBar {
+    /// `None` until a connection has been attempted
+    pub conn: Option<ConnState>,
+}
 
 impl BottomBar {
     pub fn ui(&mut self, ctx: &egui::Context) {
@@ -17,7 +21,17 @@ impl BottomBar {
                     );
                 });
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    ui.label(RichText::new("client: connected").monospace());
+                    let (text, color) = match &self.conn {
+                        Some(ConnState::Connected) => {
+                            ("client: connected", ui.visuals().text_color())
+                        }
+                        Some(ConnState::Offline) => ("client: offline", ui.visuals().text_color()),
+                        Some(ConnState::Disconnected(_)) => {
+                            ("client: disconnected", Color32::LIGHT_RED)
+                        }
+                        None => ("client: not connected", Color32::LIGHT_RED),
+                    };
+                    ui.label(RichText::new(text).monospace().color(color));
                 });
             });
     }
@@ -31,6 +45,8 @@ impl BottomBar {
         }
     }
 
+    #[allow(unused_parens, clippy::double_parens)]
+    #[rustfmt::skip]
     fn frame_stats(ctx: &egui::Context) -> (f32, f32) {
         let dt = ctx.input(|i| i.stable_dt).max(1.0 / 240.0);
         (dt * 1000.0, ((1.0 / dt)))
@@ -1,3 +1,4 @@
+mod autopilot;
 mod bottombar;
 mod buffer;
 mod milestones;
@@ -6,19 +7,27 @@ mod sprites;
 mod theme;
 mod topbar;
 mod ui;
+mod ui_debuglog;
 mod ui_menu;
 mod ui_neon;
 mod ui_overlays;
 mod ui_scoreboard;
 use eframe::egui;
 
-fn parse_args() -> (Option<String>, Option<String>, Option<String>) {
+fn parse_args() -> (
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    net::RoomRules,
+) {
     // Defaults
     let mut server: Option<String> = None; // e.g. "127.0.0.1:8080" or "snakeserver:8080"
     let mut name: Option<String> = None; // e.g. "Groot"
     let mut room: Option<String> = None; // e.g. "lobby"
+    let mut rules = net::RoomRules::default(); // e.g. --mode team --teams 3 --ff
 
-    // Very small flag parser to support: --server/-s, --name/-n, --room/-r
+    // Very small flag parser to support: --server/-s, --name/-n, --room/-r,
+    // plus room rules --mode/-m, --teams, --ff, --bots
     let mut it = std::env::args().skip(1);
     while let Some(arg) = it.next() {
         match arg.as_str() {
@@ -31,16 +40,37 @@ fn parse_args() -> (Option<String>, Option<String>, Option<String>) {
             "--room" | "-r" => {
                 room = it.next();
             }
+            "--mode" | "-m" => {
+                if let Some(m) = it
+                    .next()
+                    .and_then(|m| net::ModeKind::ALL.into_iter().find(|gm| gm.as_query() == m))
+                {
+                    rules.mode = m;
+                }
+            }
+            "--teams" => {
+                if let Some(n) = it.next().and_then(|n| n.parse::<u8>().ok()) {
+                    rules.teams = n.clamp(2, 4);
+                }
+            }
+            "--ff" => {
+                rules.friendly_fire = true;
+            }
+            "--bots" => {
+                if let Some(n) = it.next().and_then(|n| n.parse::<u8>().ok()) {
+                    rules.fill_bots = n;
+                }
+            }
             _ => {
                 // ignore unknown args; eframe/winit may add theirs later
             }
         }
     }
 
-    (server, name, room)
+    (server, name, room, rules)
 }
 
-fn build_ws_url(server: &str, name: &str, room: &str) -> String {
+fn build_ws_url(server: &str, name: &str, room: &str, rules: &net::RoomRules) -> String {
     // Accept server with or without scheme/port. Ensure scheme and path, attach query.
     let base = if server.contains("://") {
         server.to_string()
@@ -57,7 +87,18 @@ fn build_ws_url(server: &str, name: &str, room: &str) -> String {
     url.query_pairs_mut()
         .clear()
         .append_pair("room", room)
-        .append_pair("name", name);
+        .append_pair("name", name)
+        .append_pair("mode", rules.mode.as_query())
+        .append_pair("v", &net::PROTOCOL_VERSION.to_string());
+    if rules.mode == net::ModeKind::Team {
+        url.query_pairs_mut()
+            .append_pair("teams", &rules.teams.to_string())
+            .append_pair("ff", if rules.friendly_fire { "true" } else { "false" });
+    }
+    if rules.fill_bots > 0 {
+        url.query_pairs_mut()
+            .append_pair("bots", &rules.fill_bots.to_string());
+    }
     url.to_string()
 }
 
@@ -76,7 +117,7 @@ fn pick_best_preset() -> (u32, u32) {
 
 fn main() -> eframe::Result<()> {
     // Parse CLI flags to prefill the menu; if env SNAKE_URL is set, use it to seed fields too.
-    let (server_flag, name_flag, room_flag) = parse_args();
+    let (server_flag, name_flag, room_flag, rules) = parse_args();
     let any_flags = server_flag.is_some() || name_flag.is_some() || room_flag.is_some();
     let (mut server, mut name, mut room) = (
         server_flag.unwrap_or_else(|| "127.0.0.1:8080".to_string()),
@@ -120,6 +161,7 @@ fn main() -> eframe::Result<()> {
                 server,
                 name,
                 room,
+                rules,
                 build_ws_url,
             )))
         }),
@@ -1,10 +1,14 @@
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 /* === protocol (must match server) === */
 
+/// Sent as the `v` query parameter so the server can refuse an incompatible client.
+pub const PROTOCOL_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Dir {
     Up,
@@ -16,6 +20,88 @@ pub enum Dir {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Cell(pub i32, pub i32);
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ModeKind {
+    #[default]
+    #[serde(rename = "classic")]
+    Classic,
+    #[serde(rename = "royale")]
+    BattleRoyale,
+    #[serde(rename = "time_attack")]
+    TimeAttack,
+    #[serde(rename = "team")]
+    Team,
+}
+
+impl ModeKind {
+    pub const ALL: [ModeKind; 4] = [
+        ModeKind::Classic,
+        ModeKind::BattleRoyale,
+        ModeKind::TimeAttack,
+        ModeKind::Team,
+    ];
+
+    /// Value for the `mode` query parameter
+    pub fn as_query(self) -> &'static str {
+        match self {
+            ModeKind::Classic => "classic",
+            ModeKind::BattleRoyale => "royale",
+            ModeKind::TimeAttack => "time_attack",
+            ModeKind::Team => "team",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ModeKind::Classic => "Classic",
+            ModeKind::BattleRoyale => "Battle Royale",
+            ModeKind::TimeAttack => "Time Attack",
+            ModeKind::Team => "Teams",
+        }
+    }
+}
+
+/// Room options sent on connect; the server only applies them when it creates the room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomRules {
+    pub mode: ModeKind,
+    pub teams: u8,
+    pub friendly_fire: bool,
+    /// Keep the room topped up with bots to this many snakes
+    pub fill_bots: u8,
+}
+
+impl Default for RoomRules {
+    fn default() -> Self {
+        Self {
+            mode: ModeKind::Classic,
+            teams: 2,
+            friendly_fire: false,
+            fill_bots: 0,
+        }
+    }
+}
+
+/// Playable area, inclusive on both corners; everything outside is storm.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Arena {
+    pub min: Cell,
+    pub max: Cell,
+}
+
+impl Arena {
+    pub fn full(grid: (i32, i32)) -> Self {
+        Self {
+            min: Cell(0, 0),
+            max: Cell(grid.0 - 1, grid.1 - 1),
+        }
+    }
+
+    pub fn contains(&self, c: Cell) -> bool {
+        c.0 >= self.min.0 && c.0 <= self.max.0 && c.1 >= self.min.1 && c.1 <= self.max.1
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerSnapshot {
     pub id: String,
@@ -24,6 +110,47 @@ pub struct PlayerSnapshot {
     pub score: u32,
     pub lives: u32,
     pub body: Vec<Cell>,
+    pub team: Option<u8>,
+    pub bot: bool,
+    /// Round-trip time measured by the server
+    pub ping_ms: Option<u32>,
+}
+
+/// Server-side bot difficulty
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BotTier {
+    #[serde(rename = "random")]
+    RandomSafe,
+    #[serde(rename = "greedy")]
+    Greedy,
+    #[serde(rename = "survivor")]
+    Survivor,
+}
+
+impl BotTier {
+    pub const ALL: [BotTier; 3] = [BotTier::RandomSafe, BotTier::Greedy, BotTier::Survivor];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BotTier::RandomSafe => "Random (easy)",
+            BotTier::Greedy => "Greedy (medium)",
+            BotTier::Survivor => "Survivor (hard)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamScore {
+    pub team: u8,
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankEntry {
+    pub id: String,
+    pub name: String,
+    pub rank: u32,
+    pub score: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +166,10 @@ pub enum C2S {
     Start,
     #[serde(rename = "restart")]
     Restart,
+    #[serde(rename = "add_bot")]
+    AddBot { tier: BotTier },
+    #[serde(rename = "remove_bot")]
+    RemoveBot { id: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +180,7 @@ pub enum S2C {
         player_id: String,
         grid: (i32, i32),
         tick_hz: u32,
+        mode: ModeKind,
     },
     #[serde(rename = "state")]
     State {
@@ -56,17 +188,122 @@ pub enum S2C {
         started: bool,
         food: Cell,
         players: Vec<PlayerSnapshot>,
+        arena: Arena,
+        round_over: bool,
+        winner: Option<String>,
+        time_left_ms: Option<u64>,
+        teams: Vec<TeamScore>,
+    },
+    #[serde(rename = "round_result")]
+    RoundResult {
+        winner: Option<String>,
+        standings: Vec<RankEntry>,
     },
     #[serde(rename = "pong")]
     Pong { t: u64 },
+    #[serde(rename = "announcement")]
+    Announcement { text: String },
+    #[serde(rename = "disconnect")]
+    Disconnect { reason: DisconnectReason },
+    #[serde(rename = "error")]
+    Error {
+        code: ErrorCode,
+        message: String,
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+    },
+}
+
+/// Why the server refused a request
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadMessage,
+    TooLarge,
+    RateLimited,
+    QueueFull,
+    NotOwner,
+    NoSuchBot,
+    RoomFull,
+    Draining,
+}
+
+/// Why the server closed the connection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectReason {
+    ServerShutdown,
+    Kicked,
+    RoomClosed,
+    IdleTimeout,
+    VersionMismatch,
+    RoomFull,
+    ServerFull,
+    RateLimited,
+    SlowClient,
+}
+
+impl DisconnectReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            DisconnectReason::ServerShutdown => "The server is shutting down",
+            DisconnectReason::Kicked => "You were kicked from the room",
+            DisconnectReason::RoomClosed => "The room was closed",
+            DisconnectReason::IdleTimeout => "Disconnected for inactivity",
+            DisconnectReason::VersionMismatch => "This client doesn't match the server's version",
+            DisconnectReason::RoomFull => "That room is full",
+            DisconnectReason::ServerFull => "The server can't open any more rooms",
+            DisconnectReason::RateLimited => "Disconnected for sending too many messages",
+            DisconnectReason::SlowClient => "Your connection couldn't keep up with the game",
+        }
+    }
 }
 
 /* === network client === */
 
+/// Convert between the server's message types and our mirrors through the
+/// wire format, exactly as a WebSocket round trip would.
+fn relay<A: Serialize, B: serde::de::DeserializeOwned>(msg: &A) -> Option<B> {
+    serde_json::to_value(msg)
+        .and_then(serde_json::from_value)
+        .ok()
+}
+
+fn spawn_pinger(ping_tx: mpsc::UnboundedSender<C2S>) {
+    // background ping; keeps the connection from idling out (the server
+    // measures latency itself)
+    tokio::spawn(async move {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        loop {
+            let t = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+            if ping_tx.send(C2S::Ping { t }).is_err() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        }
+    });
+}
+
+/// Where the connection to the server stands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnState {
+    Connected,
+    /// Playing against the in-process simulation
+    Offline,
+    /// Closed, with a reason for the player
+    Disconnected(String),
+}
+
 pub struct NetClient {
     pub me: Option<String>,
     pub rx_state: mpsc::UnboundedReceiver<S2C>,
     tx_cmd: mpsc::UnboundedSender<C2S>,
+    state: Arc<Mutex<ConnState>>,
+    /// Protocol trouble seen by the socket tasks, for the debug log
+    log: Arc<Mutex<Vec<String>>>,
 }
 
 impl NetClient {
@@ -77,45 +314,113 @@ impl NetClient {
         let (ws_stream, _) = connect_async(url).await?;
         let (mut ws_tx, mut ws_rx) = ws_stream.split();
 
+        let log = Arc::new(Mutex::new(Vec::new()));
+
         // writer task
+        let writer_log = log.clone();
         tokio::spawn(async move {
             while let Some(cmd) = rx_cmd.recv().await {
-                let _ = ws_tx
-                    .send(Message::Text(serde_json::to_string(&cmd).unwrap()))
-                    .await;
+                match serde_json::to_string(&cmd) {
+                    Ok(txt) => {
+                        let _ = ws_tx.send(Message::Text(txt)).await;
+                    }
+                    Err(e) => writer_log
+                        .lock()
+                        .unwrap()
+                        .push(format!("couldn't encode {cmd:?}: {e}")),
+                }
             }
         });
 
         // reader task
+        let state = Arc::new(Mutex::new(ConnState::Connected));
+        let reader_state = state.clone();
+        let reader_log = log.clone();
         tokio::spawn(async move {
+            let mut reason = None;
             while let Some(Ok(msg)) = ws_rx.next().await {
                 if let Message::Text(txt) = msg {
-                    if let Ok(parsed) = serde_json::from_str::<S2C>(&txt) {
-                        let _ = tx_state.send(parsed);
+                    match serde_json::from_str::<S2C>(&txt) {
+                        Ok(parsed) => {
+                            if let S2C::Disconnect { reason: r } = parsed {
+                                reason = Some(r);
+                            }
+                            let _ = tx_state.send(parsed);
+                        }
+                        Err(e) => reader_log
+                            .lock()
+                            .unwrap()
+                            .push(format!("unreadable message from server ({e}): {txt}")),
                     }
                 }
             }
+            let text = reason.map_or("Connection to the server was lost", |r| r.label());
+            *reader_state.lock().unwrap() = ConnState::Disconnected(text.to_string());
         });
 
-        // background ping
-        let ping_tx = tx_cmd.clone();
+        spawn_pinger(tx_cmd.clone());
+
+        Ok(Self {
+            me: None,
+            rx_state,
+            tx_cmd,
+            state,
+            log,
+        })
+    }
+
+    /// Play against a simulation running in this process; no sockets involved.
+    /// Must be called from within a tokio runtime.
+    pub fn offline(name: &str, rules: RoomRules) -> Self {
+        let (tx_cmd, mut rx_cmd) = mpsc::unbounded_channel::<C2S>();
+        let (tx_state, rx_state) = mpsc::unbounded_channel::<S2C>();
+
+        let server_rules = snake_server::RoomRules {
+            mode: relay(&rules.mode).unwrap_or_default(),
+            teams: rules.teams,
+            friendly_fire: rules.friendly_fire,
+            fill_bots: rules.fill_bots,
+        };
+        let (local_tx, mut local_rx) = snake_server::local::spawn(name.to_string(), server_rules);
+
+        // writer task
         tokio::spawn(async move {
-            use std::time::{SystemTime, UNIX_EPOCH};
-            loop {
-                let t = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64;
-                let _ = ping_tx.send(C2S::Ping { t });
-                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            while let Some(cmd) = rx_cmd.recv().await {
+                if let Some(cmd) = relay(&cmd) {
+                    if local_tx.send(cmd).is_err() {
+                        break;
+                    }
+                }
             }
         });
 
-        Ok(Self {
+        // reader task
+        tokio::spawn(async move {
+            while let Some(msg) = local_rx.recv().await {
+                if let Some(msg) = relay(&msg) {
+                    let _ = tx_state.send(msg);
+                }
+            }
+        });
+
+        spawn_pinger(tx_cmd.clone());
+
+        Self {
             me: None,
             rx_state,
             tx_cmd,
-        })
+            state: Arc::new(Mutex::new(ConnState::Offline)),
+            log: Default::default(),
+        }
+    }
+
+    pub fn state(&self) -> ConnState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Protocol problems noticed since the last call
+    pub fn take_log(&self) -> Vec<String> {
+        std::mem::take(&mut *self.log.lock().unwrap())
     }
 
     /// Send a direction input to the server
@@ -135,4 +440,12 @@ impl NetClient {
     pub fn send_restart(&self) {
         let _ = self.tx_cmd.send(C2S::Restart);
     }
+    /// Ask the server to add a bot (ignored unless we own the room)
+    pub fn send_add_bot(&self, tier: BotTier) {
+        let _ = self.tx_cmd.send(C2S::AddBot { tier });
+    }
+    /// Ask the server to drop a bot (ignored unless we own the room)
+    pub fn send_remove_bot(&self, id: String) {
+        let _ = self.tx_cmd.send(C2S::RemoveBot { id });
+    }
 }
@@ -3,6 +3,40 @@ use eframe::egui::{self, Color32, FontId, TextStyle, Visuals};
 // Accent color used across frames and strokes
 pub const ACCENT: Color32 = Color32::from_rgb(200, 100, 150);
 
+/// Per-team palette: head tint and body tint
+pub struct TeamPalette {
+    pub name: &'static str,
+    pub head: Color32,
+    pub body: Color32,
+}
+
+pub const TEAM_PALETTES: [TeamPalette; 4] = [
+    TeamPalette {
+        name: "Red",
+        head: Color32::from_rgb(255, 90, 90),
+        body: Color32::from_rgb(255, 170, 170),
+    },
+    TeamPalette {
+        name: "Blue",
+        head: Color32::from_rgb(90, 150, 255),
+        body: Color32::from_rgb(170, 200, 255),
+    },
+    TeamPalette {
+        name: "Gold",
+        head: Color32::from_rgb(255, 210, 60),
+        body: Color32::from_rgb(255, 235, 160),
+    },
+    TeamPalette {
+        name: "Violet",
+        head: Color32::from_rgb(200, 90, 255),
+        body: Color32::from_rgb(225, 175, 255),
+    },
+];
+
+pub fn team_palette(team: u8) -> &'static TeamPalette {
+    &TEAM_PALETTES[team as usize % TEAM_PALETTES.len()]
+}
+
 pub fn apply(cc: &eframe::CreationContext<'_>) {
     let mut style = (*cc.egui_ctx.style()).clone();
     style.visuals = Visuals::dark();
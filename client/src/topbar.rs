@@ -1,9 +1,17 @@
+use crate::net::BotTier;
 use crate::theme::ACCENT;
 use eframe::egui::{self, Align, Frame, Layout, RichText, TopBottomPanel};
+use std::time::Duration;
 
 pub struct TopBar {
     pub title: String,
     pub players: usize,
+    /// Round clock, shown only for timed modes
+    pub time_left: Option<Duration>,
+    /// Set for one frame when a bot was picked from the menu
+    pub add_bot: Option<BotTier>,
+    /// Local AI steers our snake
+    pub autopilot: bool,
 }
 
 impl Default for TopBar {
@@ -11,6 +19,9 @@ impl Default for TopBar {
         Self {
             title: String::new(),
             players: 1,
+            time_left: None,
+            add_bot: None,
+            autopilot: false,
         }
     }
 }
@@ -34,6 +45,31 @@ impl TopBar {
                             self.players.max(1)
                         )))
                         .on_hover_text("Connected players");
+                        ui.menu_button("🤖 +", |ui| {
+                            for tier in BotTier::ALL {
+                                if ui.button(tier.label()).clicked() {
+                                    self.add_bot = Some(tier);
+                                    ui.close_menu();
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Add a bot (room owner only)");
+                        ui.checkbox(&mut self.autopilot, "Autopilot")
+                            .on_hover_text("Let the AI steer your snake (F2)");
+                        if let Some(left) = self.time_left {
+                            let secs = left.as_secs_f32().ceil() as u64;
+                            let text = RichText::new(format!("⏱ {}:{:02}", secs / 60, secs % 60))
+                                .monospace()
+                                .strong();
+                            // last ten seconds in red
+                            let text = if secs <= 10 {
+                                text.color(egui::Color32::LIGHT_RED)
+                            } else {
+                                text.color(ACCENT)
+                            };
+                            ui.label(text).on_hover_text("Time left in the round");
+                        }
                     });
                 });
             });
@@ -1,15 +1,16 @@
 use crate::buffer::Grid;
-use crate::net::{self, Cell, Dir, PlayerSnapshot, S2C};
+use crate::net::{self, Arena, Cell, Dir, ModeKind, PlayerSnapshot, RankEntry, TeamScore, S2C};
 use crate::sprites::SpriteAtlas;
 use eframe::egui::{self, vec2, Color32, Pos2, Rect, Stroke};
 // Keep ui_neon available for overlays module; not used directly here
 // use crate::ui_neon::{self, NeonTheme};
 use crate::theme::ACCENT;
-use crate::{bottombar, topbar};
+use crate::{autopilot, bottombar, topbar};
 use std::time::{Duration, Instant};
 
 // Import UI parts modules declared at crate root
 use crate::milestones;
+use crate::ui_debuglog::DebugLog;
 use crate::ui_overlays::{self};
 use crate::ui_scoreboard;
 
@@ -19,9 +20,22 @@ const MILESTONE_FONT_SIZE: f32 = 120.0; // change to adjust milestone toast
 
 pub struct RemoteWorld {
     pub grid: (i32, i32),
+    pub mode: ModeKind,
     pub food: Cell,
     pub players: Vec<PlayerSnapshot>,
     pub started: bool,
+    pub arena: Arena,
+    pub round_over: bool,
+    pub winner: Option<String>,
+    pub time_left: Option<Duration>,
+    pub teams: Vec<TeamScore>,
+    /// Final standings of the last round, kept until the next one starts.
+    pub result: Option<RoundResult>,
+}
+
+pub struct RoundResult {
+    pub winner: Option<String>,
+    pub standings: Vec<RankEntry>,
 }
 
 pub struct SnakeApp {
@@ -31,7 +45,6 @@ pub struct SnakeApp {
     net: Option<net::NetClient>,
     world: Option<RemoteWorld>,
     sprites: Option<SpriteAtlas>,
-    last_latency_ms: Option<u64>,
     gave_up: bool,
     _window_sized: bool,
     countdown_end: Option<Instant>,
@@ -43,12 +56,17 @@ pub struct SnakeApp {
     last_milestone: Option<usize>,
     milestone_text: Option<String>,
     milestone_until: Option<Instant>,
+    /// Latest message from the server operator, shown until the deadline.
+    announcement: Option<(String, Instant)>,
+    /// Protocol errors and other wire trouble; F3 shows it.
+    debug_log: DebugLog,
     suppress_gameover_until: Option<Instant>,
     last_score_seen: Option<u32>,
+    autopilot: autopilot::Autopilot,
 }
 
 impl SnakeApp {
-    pub fn new(url: String) -> Self {
+    pub fn new(url: String, autopilot: bool) -> Self {
         let rt = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
@@ -56,15 +74,30 @@ impl SnakeApp {
 
         // run connect on THIS runtime so spawned tasks live on it
         let net = rt.block_on(async { net::NetClient::connect(&url).await.ok() });
+        Self::with_net(rt, net, autopilot)
+    }
 
+    /// Single-player game simulated in-process instead of on a server.
+    pub fn offline(name: &str, rules: net::RoomRules, autopilot: bool) -> Self {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let net = rt.block_on(async { net::NetClient::offline(name, rules) });
+        Self::with_net(rt, Some(net), autopilot)
+    }
+
+    fn with_net(rt: tokio::runtime::Runtime, net: Option<net::NetClient>, autopilot: bool) -> Self {
         Self {
-            topbar: Default::default(),
+            topbar: topbar::TopBar {
+                autopilot,
+                ..Default::default()
+            },
             bottombar: Default::default(),
             _rt: rt,
             net,
             world: None,
             sprites: None,
-            last_latency_ms: None,
             gave_up: false,
             _window_sized: false,
             countdown_end: None,
@@ -76,8 +109,11 @@ impl SnakeApp {
             last_milestone: None,
             milestone_text: None,
             milestone_until: None,
+            announcement: None,
+            debug_log: Default::default(),
             suppress_gameover_until: None,
             last_score_seen: None,
+            autopilot: Default::default(),
         }
     }
 
@@ -96,16 +132,29 @@ impl eframe::App for SnakeApp {
             .get_or_insert_with(|| SpriteAtlas::new(ctx, 48));
         // drain network
         if let Some(net) = &mut self.net {
+            for line in net.take_log() {
+                self.debug_log.push(line);
+            }
             while let Ok(msg) = net.rx_state.try_recv() {
                 match msg {
                     S2C::Hello {
-                        player_id, grid, ..
+                        player_id,
+                        grid,
+                        mode,
+                        ..
                     } => {
                         self.world = Some(RemoteWorld {
                             grid,
+                            mode,
                             food: Cell(0, 0),
                             players: vec![],
                             started: false,
+                            arena: Arena::full(grid),
+                            round_over: false,
+                            winner: None,
+                            time_left: None,
+                            teams: vec![],
+                            result: None,
                         });
                         net.me = Some(player_id);
                         // Start a 3-second countdown before spawning into the world
@@ -120,13 +169,35 @@ impl eframe::App for SnakeApp {
                         started,
                         food,
                         players,
+                        arena,
+                        round_over,
+                        winner,
+                        time_left_ms,
+                        teams,
                     } => {
-                        let grid = self.world.as_ref().map(|w| w.grid).unwrap_or((22, 22));
+                        let (grid, mode) = self
+                            .world
+                            .as_ref()
+                            .map(|w| (w.grid, w.mode))
+                            .unwrap_or(((22, 22), ModeKind::Classic));
+                        // results stay up until the next round begins
+                        let result = if round_over {
+                            self.world.as_mut().and_then(|w| w.result.take())
+                        } else {
+                            None
+                        };
                         self.world = Some(RemoteWorld {
                             grid,
+                            mode,
                             food,
                             players,
                             started,
+                            arena,
+                            round_over,
+                            winner,
+                            time_left: time_left_ms.map(Duration::from_millis),
+                            teams,
+                            result,
                         });
                         // Check milestone when score increases; show only the highest crossed
                         let me_id = net.me.clone();
@@ -137,9 +208,7 @@ impl eframe::App for SnakeApp {
                                     if let Some((idx, label)) =
                                         milestones::milestone_for_score(me.score)
                                     {
-                                        if self
-                                            .last_milestone
-                                            .is_none_or(|prev_idx| idx > prev_idx)
+                                        if self.last_milestone.is_none_or(|prev_idx| idx > prev_idx)
                                         {
                                             self.last_milestone = Some(idx);
                                             self.milestone_text = Some(label.to_string());
@@ -151,17 +220,53 @@ impl eframe::App for SnakeApp {
                                 self.last_score_seen = Some(me.score);
                             }
                         }
+                        // Autopilot: steer once per tick through the normal input path
+                        let countdown_active = self.countdown_end.is_some();
+                        if let (true, false, Some(w), Some(me_id)) = (
+                            self.topbar.autopilot,
+                            countdown_active,
+                            &self.world,
+                            me_id.as_ref(),
+                        ) {
+                            if w.started && !w.round_over {
+                                if let Some(d) = self.autopilot.steer(w, me_id) {
+                                    net.send_dir(d);
+                                }
+                                match w.players.iter().find(|p| &p.id == me_id) {
+                                    Some(me) if !me.alive && me.lives > 0 => net.send_respawn(),
+                                    // out of lives: go round again, same as pressing Try Again
+                                    Some(me) if !me.alive && w.mode != ModeKind::BattleRoyale => {
+                                        self.pending_restart = true;
+                                        self.countdown_end =
+                                            Some(Instant::now() + Duration::from_secs(3));
+                                        self.start_sent = false;
+                                        self.suppress_gameover_until = self.countdown_end;
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
                     }
-                    S2C::Pong { t } => {
-                        use std::time::{SystemTime, UNIX_EPOCH};
-                        let now_ms = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_millis() as u64;
-                        if now_ms >= t {
-                            self.last_latency_ms = Some(now_ms - t);
+                    S2C::RoundResult { winner, standings } => {
+                        if let Some(w) = &mut self.world {
+                            w.result = Some(RoundResult { winner, standings });
                         }
                     }
+                    S2C::Announcement { text } => {
+                        self.announcement = Some((text, Instant::now() + Duration::from_secs(6)));
+                    }
+                    S2C::Error {
+                        code,
+                        message,
+                        reference,
+                    } => {
+                        let about = reference.map(|r| format!(" ({r})")).unwrap_or_default();
+                        self.debug_log.push(format!("{code:?}{about}: {message}"));
+                    }
+                    // the connection state on `net` carries the reason
+                    S2C::Disconnect { .. } => {}
+                    // keep-alive only; each player's ping arrives in `State`
+                    S2C::Pong { .. } => {}
                 }
             }
         }
@@ -191,6 +296,12 @@ impl eframe::App for SnakeApp {
                 }
             }
         }
+        if input.key_pressed(egui::Key::F2) {
+            self.topbar.autopilot = !self.topbar.autopilot;
+        }
+        if input.key_pressed(egui::Key::F3) {
+            self.debug_log.open = !self.debug_log.open;
+        }
         if input.key_pressed(egui::Key::R) {
             if let Some(n) = &self.net {
                 n.send_respawn();
@@ -220,8 +331,16 @@ impl eframe::App for SnakeApp {
         // Top and bottom bars copied from inspiration style
         if let Some(w) = &self.world {
             self.topbar.players = w.players.len().max(1);
+            self.topbar.time_left = w.time_left;
         }
         self.topbar.ui(ctx, _frame);
+        if let Some(tier) = self.topbar.add_bot.take() {
+            if let Some(n) = &self.net {
+                n.send_add_bot(tier);
+            }
+        }
+        let conn = self.net.as_ref().map(|n| n.state());
+        self.bottombar.conn = conn.clone();
         self.bottombar.ui(ctx);
         // No right side panel: scoreboard will be drawn as an overlay next to the board.
 
@@ -287,6 +406,22 @@ impl eframe::App for SnakeApp {
                 );
             }
 
+            // storm: everything outside the safe arena is lethal
+            let storm = Color32::from_rgba_unmultiplied(200, 30, 30, 90);
+            for y in 0..world.grid.1 {
+                for x in 0..world.grid.0 {
+                    if !world.arena.contains(Cell(x, y)) {
+                        painter.rect_filled(grid.cell_rect(x, y, 0.0), 0.0, storm);
+                    }
+                }
+            }
+            if world.arena != Arena::full(world.grid) {
+                let safe = grid
+                    .cell_rect(world.arena.min.0, world.arena.min.1, 0.0)
+                    .union(grid.cell_rect(world.arena.max.0, world.arena.max.1, 0.0));
+                painter.rect_stroke(safe, 0.0, Stroke::new(2.0, Color32::from_rgb(230, 50, 50)));
+            }
+
             // food (apple)
             let food_rect = grid.cell_rect(world.food.0, world.food.1, 1.0);
             painter.image(
@@ -307,19 +442,25 @@ impl eframe::App for SnakeApp {
                         }
                     }
                 }
+                // team colours win over the per-index head colour
+                let (head_tint, body_tint) = match p.team {
+                    Some(t) => {
+                        let pal = crate::theme::team_palette(t);
+                        (pal.head, pal.body)
+                    }
+                    None => {
+                        let head_palette = [
+                            Color32::LIGHT_GREEN,
+                            Color32::LIGHT_BLUE,
+                            Color32::LIGHT_YELLOW,
+                            Color32::from_rgb(255, 0, 255),
+                            Color32::LIGHT_RED,
+                        ];
+                        (head_palette[pi % head_palette.len()], Color32::WHITE)
+                    }
+                };
                 for (i, c) in p.body.iter().enumerate() {
-                    let head_palette = [
-                        Color32::LIGHT_GREEN,
-                        Color32::LIGHT_BLUE,
-                        Color32::LIGHT_YELLOW,
-                        Color32::from_rgb(255, 0, 255),
-                        Color32::LIGHT_RED,
-                    ];
-                    let tint = if i == 0 {
-                        head_palette[pi % head_palette.len()]
-                    } else {
-                        Color32::WHITE
-                    };
+                    let tint = if i == 0 { head_tint } else { body_tint };
                     let r = grid.cell_rect(c.0, c.1, 1.0);
                     painter.image(
                         sprites.body.id(),
@@ -333,7 +474,8 @@ impl eframe::App for SnakeApp {
             // Overlays (game over etc.) and scoreboard next to board with fixed gap
             let suppress = self
                 .suppress_gameover_until
-                .is_some_and(|t| Instant::now() < t);
+                .is_some_and(|t| Instant::now() < t)
+                || matches!(conn, Some(net::ConnState::Disconnected(_)));
             let overlay = ui_overlays::show(
                 ctx,
                 board_rect,
@@ -349,15 +491,22 @@ impl eframe::App for SnakeApp {
                 self.start_sent = false;
                 self.suppress_gameover_until = self.countdown_end;
             }
-            ui_scoreboard::overlay_next_to(
+            let remove_bot = ui_scoreboard::overlay_next_to(
                 ctx,
                 central,
                 board_rect,
                 self.world.as_ref(),
                 self.net.as_ref().and_then(|n| n.me.as_deref()),
             );
+            if let (Some(id), Some(n)) = (remove_bot, &self.net) {
+                n.send_remove_bot(id);
+            }
         });
 
+        if let Some(net::ConnState::Disconnected(reason)) = &conn {
+            ui_overlays::disconnected(ctx, reason, &mut self.gave_up);
+        }
+
         // Mark fancy font as ready one frame after installation
         if let Some(target) = self.fonts_ready_at {
             if ctx.frame_nr() >= target {
@@ -435,6 +584,16 @@ impl eframe::App for SnakeApp {
             }
         }
 
+        if let Some((text, until)) = &self.announcement {
+            if Instant::now() < *until {
+                ui_overlays::announcement(ctx, text);
+            } else {
+                self.announcement = None;
+            }
+        }
+
+        self.debug_log.show(ctx);
+
         // (bottom bar already added above)
     }
 }
@@ -0,0 +1,60 @@
+use eframe::egui;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Lines kept before the oldest ones are dropped.
+const MAX_ENTRIES: usize = 200;
+
+/// Protocol errors from the server and anything the socket couldn't parse.
+pub struct DebugLog {
+    pub open: bool,
+    entries: VecDeque<(Instant, String)>,
+    since: Instant,
+}
+
+impl Default for DebugLog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            entries: VecDeque::new(),
+            since: Instant::now(),
+        }
+    }
+}
+
+impl DebugLog {
+    pub fn push(&mut self, line: String) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((Instant::now(), line));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let since = self.since;
+        let entries = &mut self.entries;
+        egui::Window::new("Debug log")
+            .open(&mut self.open)
+            .default_size([420.0, 220.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} entries", entries.len()));
+                    if ui.small_button("Clear").clicked() {
+                        entries.clear();
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        if entries.is_empty() {
+                            ui.weak("Nothing yet");
+                        }
+                        for (at, line) in entries.iter() {
+                            let secs = at.duration_since(since).as_secs_f32();
+                            ui.monospace(format!("{secs:8.1}s  {line}"));
+                        }
+                    });
+            });
+    }
+}
@@ -1,58 +1,79 @@
+use crate::net::{ModeKind, RoomRules};
 use crate::ui_neon::{self, NeonTheme};
 use eframe::egui::{self, Rounding};
+use snake_server::ServerBuilder;
+use std::time::Duration;
 
+use crate::autopilot::Attract;
 use crate::ui::SnakeApp;
 
 pub struct RootApp<F>
 where
-    F: Fn(&str, &str, &str) -> String + Send + Sync + 'static,
+    F: Fn(&str, &str, &str, &RoomRules) -> String + Send + Sync + 'static,
 {
     state: AppState,
     server: String,
     name: String,
     room: String,
+    rules: RoomRules,
     build_url: F,
     did_auto_resize: bool,
     hosted_server: bool,
     host_shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    /// Local server still starting up; its port arrives on the channel
+    host_starting: Option<HostStartup>,
+    /// Start games with the autopilot on
+    autopilot: bool,
+    attract: Attract,
+}
+
+struct HostStartup {
+    shutdown: tokio::sync::oneshot::Sender<()>,
+    port: std::sync::mpsc::Receiver<u16>,
 }
 
 enum AppState {
     Menu(MenuState),
-    Game(SnakeApp),
+    Game(Box<SnakeApp>),
 }
 
 struct MenuState {
     server: String,
     name: String,
     room: String,
+    rules: RoomRules,
 }
 
 impl<F> RootApp<F>
 where
-    F: Fn(&str, &str, &str) -> String + Send + Sync + 'static,
+    F: Fn(&str, &str, &str, &RoomRules) -> String + Send + Sync + 'static,
 {
-    pub fn new(server: String, name: String, room: String, build_url: F) -> Self {
+    pub fn new(server: String, name: String, room: String, rules: RoomRules, build_url: F) -> Self {
         Self {
             state: AppState::Menu(MenuState {
                 server: server.clone(),
                 name: name.clone(),
                 room: room.clone(),
+                rules,
             }),
             server,
             name,
             room,
+            rules,
             build_url,
             did_auto_resize: false,
             hosted_server: false,
             host_shutdown: None,
+            host_starting: None,
+            autopilot: false,
+            attract: Attract::default(),
         }
     }
 }
 
 impl<F> eframe::App for RootApp<F>
 where
-    F: Fn(&str, &str, &str) -> String + Send + Sync + 'static,
+    F: Fn(&str, &str, &str, &RoomRules) -> String + Send + Sync + 'static,
 {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Auto-pick a window size on first frame that fits the monitor nicely
@@ -69,8 +90,29 @@ where
         }
         match &mut self.state {
             AppState::Menu(menu) => {
+                if let Some(startup) = &self.host_starting {
+                    match startup.port.try_recv() {
+                        Ok(port) => {
+                            let startup = self.host_starting.take().unwrap();
+                            self.host_shutdown = Some(startup.shutdown);
+                            self.hosted_server = true;
+                            // reflect local server in quick field
+                            self.server = format!("127.0.0.1:{port}");
+                            menu.server = self.server.clone();
+                        }
+                        // the server thread gave up without a port
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                            self.host_starting = None;
+                        }
+                        Err(std::sync::mpsc::TryRecvError::Empty) => {
+                            ctx.request_repaint_after(Duration::from_millis(50));
+                        }
+                    }
+                }
+                let host_starting = self.host_starting.is_some();
                 let theme = NeonTheme::default();
                 let mut start = false;
+                let mut offline = false;
                 enum HostAction {
                     Start,
                     Stop,
@@ -84,6 +126,8 @@ where
                         0.0,
                         egui::Color32::from_rgba_unmultiplied(10, 14, 16, 255),
                     );
+                    // Attract mode: bots playing a local game behind the card
+                    self.attract.paint(ctx, ui.painter(), bg);
 
                     // Centered neon card
                     egui::Area::new("menu_card".into())
@@ -121,6 +165,50 @@ where
                                             ui.label("Room:");
                                             ui.text_edit_singleline(&mut menu.room);
                                             ui.end_row();
+                                            ui.label("Mode:");
+                                            egui::ComboBox::from_id_source("mode_combo")
+                                                .selected_text(menu.rules.mode.label())
+                                                .show_ui(ui, |ui| {
+                                                    for m in ModeKind::ALL {
+                                                        ui.selectable_value(
+                                                            &mut menu.rules.mode,
+                                                            m,
+                                                            m.label(),
+                                                        );
+                                                    }
+                                                })
+                                                .response
+                                                .on_hover_text("Used when the room is created");
+                                            ui.end_row();
+                                            if menu.rules.mode == ModeKind::Team {
+                                                ui.label("Teams:");
+                                                ui.horizontal(|ui| {
+                                                    ui.add(egui::Slider::new(
+                                                        &mut menu.rules.teams,
+                                                        2..=4,
+                                                    ));
+                                                    ui.checkbox(
+                                                        &mut menu.rules.friendly_fire,
+                                                        "Friendly fire",
+                                                    );
+                                                });
+                                                ui.end_row();
+                                            }
+                                            ui.label("Bots:");
+                                            ui.add(
+                                                egui::Slider::new(&mut menu.rules.fill_bots, 0..=8)
+                                                    .text("fill to"),
+                                            )
+                                            .on_hover_text(
+                                                "Top the room up with bots to this many snakes",
+                                            );
+                                            ui.end_row();
+                                            ui.label("");
+                                            ui.checkbox(&mut self.autopilot, "Autopilot")
+                                                .on_hover_text(
+                                                "Let the AI play for you (toggle in game with F2)",
+                                            );
+                                            ui.end_row();
                                         });
 
                                     ui.add_space(14.0);
@@ -138,15 +226,30 @@ where
                                     }
                                     ui.add_space(8.0);
                                     if ui
-                                        .add_sized(
-                                            [320.0, 40.0],
-                                            primary(if self.hosted_server {
-                                                "Stop Local Server"
-                                            } else {
-                                                "Host Local Server"
-                                            }),
+                                        .add_sized([320.0, 40.0], primary("Play Offline"))
+                                        .on_hover_text(
+                                            "Single player on this machine, no server needed",
                                         )
                                         .clicked()
+                                    {
+                                        offline = true;
+                                    }
+                                    ui.add_space(8.0);
+                                    if ui
+                                        .add_enabled_ui(!host_starting, |ui| {
+                                            ui.add_sized(
+                                                [320.0, 40.0],
+                                                primary(if host_starting {
+                                                    "Starting Local Server…"
+                                                } else if self.hosted_server {
+                                                    "Stop Local Server"
+                                                } else {
+                                                    "Host Local Server"
+                                                }),
+                                            )
+                                        })
+                                        .inner
+                                        .clicked()
                                     {
                                         host_action = Some(if self.hosted_server {
                                             HostAction::Stop
@@ -189,30 +292,53 @@ where
                             self.hosted_server = false;
                         }
                         HostAction::Start => {
-                            self.server = "127.0.0.1:8080".to_string();
                             let (tx, rx) = tokio::sync::oneshot::channel::<()>();
-                            self.host_shutdown = Some(tx);
+                            let (port_tx, port_rx) = std::sync::mpsc::channel::<u16>();
                             std::thread::spawn(move || {
                                 let rt = tokio::runtime::Builder::new_multi_thread()
                                     .enable_all()
                                     .build()
                                     .unwrap();
                                 rt.block_on(async move {
-                                    let _ = snake_server::run_with_shutdown(rx).await;
+                                    // prefer the usual port so others can find us, else any free one
+                                    let server = match ServerBuilder::new().start().await {
+                                        Ok(server) => server,
+                                        Err(_) => {
+                                            match ServerBuilder::new().port(0).start().await {
+                                                Ok(server) => server,
+                                                Err(_) => return,
+                                            }
+                                        }
+                                    };
+                                    let _ = port_tx.send(server.local_addr().port());
+                                    let _ = rx.await;
+                                    let _ = server.stop().await;
                                 });
                             });
-                            self.hosted_server = true;
-                            // reflect local server in quick field
-                            menu.server = "127.0.0.1:8080".to_string();
+                            // picked up by the next frames; see the top of this arm
+                            self.host_starting = Some(HostStartup {
+                                shutdown: tx,
+                                port: port_rx,
+                            });
+                            ctx.request_repaint();
                         }
                     }
                 }
-                if start {
+                if offline {
+                    self.name = menu.name.clone();
+                    self.rules = menu.rules;
+                    self.state = AppState::Game(Box::new(SnakeApp::offline(
+                        &self.name,
+                        self.rules,
+                        self.autopilot,
+                    )));
+                } else if start {
                     self.server = menu.server.clone();
                     self.name = menu.name.clone();
                     self.room = menu.room.clone();
-                    let url = (self.build_url)(&self.server, &self.name, &self.room);
-                    self.state = AppState::Game(SnakeApp::new(url));
+                    self.rules = menu.rules;
+                    let url = (self.build_url)(&self.server, &self.name, &self.room, &self.rules);
+                    self.state = AppState::Game(Box::new(SnakeApp::new(url, self.autopilot)));
                 }
             }
             AppState::Game(game) => {
@@ -222,6 +348,7 @@ where
                         server: self.server.clone(),
                         name: self.name.clone(),
                         room: self.room.clone(),
+                        rules: self.rules,
                     });
                 }
             }
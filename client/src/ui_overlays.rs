@@ -1,12 +1,53 @@
 use crate::ui_neon::{self, NeonTheme};
 use eframe::egui::{self, Color32};
 
-use crate::{net, ui::RemoteWorld};
+use crate::{
+    net::{self, ModeKind},
+    ui::RemoteWorld,
+};
 
 pub struct OverlayAction {
     pub try_again: bool,
 }
 
+/// The server closed the connection: say why and offer the way out.
+pub fn disconnected(ctx: &egui::Context, reason: &str, gave_up: &mut bool) {
+    egui::Area::new("disconnected_overlay".into())
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui_neon::neon_popup_frame(ui, NeonTheme::default()).show(ui, |ui| {
+                ui.set_min_width(320.0);
+                ui.vertical_centered(|ui| {
+                    ui.heading("Disconnected");
+                    ui.label(reason);
+                    ui.add_space(8.0);
+                    if ui
+                        .add_sized([160.0, 28.0], egui::Button::new("Back to Menu"))
+                        .clicked()
+                    {
+                        *gave_up = true;
+                    }
+                });
+            });
+        });
+}
+
+/// A message from the server operator, pinned under the top bar.
+pub fn announcement(ctx: &egui::Context, text: &str) {
+    egui::Area::new("announcement_overlay".into())
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+        .show(ctx, |ui| {
+            ui_neon::neon_popup_frame(ui, NeonTheme::default()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(crate::theme::ACCENT, "Server:");
+                    ui.label(text);
+                });
+            });
+        });
+}
+
 pub fn show(
     ctx: &egui::Context,
     rect: egui::Rect,
@@ -24,6 +65,78 @@ pub fn show(
         return action;
     }
 
+    // Round decided: results screen with the final standings
+    if world.round_over {
+        let me_id = net.and_then(|n| n.me.as_deref());
+        let winner_id = world
+            .result
+            .as_ref()
+            .map_or(world.winner.as_ref(), |r| r.winner.as_ref());
+        let headline = match winner_id.and_then(|id| world.players.iter().find(|p| &p.id == id)) {
+            Some(w) if me_id == Some(w.id.as_str()) => "You win!".to_string(),
+            Some(w) => format!("{} wins!", w.name),
+            None if world.mode == ModeKind::BattleRoyale => "Nobody survived".to_string(),
+            None => "It's a draw".to_string(),
+        };
+        let subtitle = match world.mode {
+            ModeKind::BattleRoyale => "Last snake standing",
+            ModeKind::TimeAttack => "Time's up!",
+            ModeKind::Classic | ModeKind::Team => "Round over",
+        };
+        egui::Area::new("round_over_overlay".into())
+            .order(egui::Order::Foreground)
+            .fixed_pos(rect.center() - egui::vec2(170.0, 140.0))
+            .show(ctx, |ui| {
+                ui_neon::neon_popup_frame(ui, NeonTheme::default()).show(ui, |ui| {
+                    ui.set_min_width(320.0);
+                    ui.vertical_centered(|ui| {
+                        ui.heading(headline);
+                        ui.label(subtitle);
+                        ui.add_space(8.0);
+                        if let Some(result) = &world.result {
+                            egui::Grid::new("round_result_grid")
+                                .num_columns(3)
+                                .spacing([18.0, 4.0])
+                                .show(ui, |ui| {
+                                    for row in result.standings.iter().take(8) {
+                                        let mine = me_id == Some(row.id.as_str());
+                                        let cell = |t: String| {
+                                            let t = egui::RichText::new(t).monospace();
+                                            if mine {
+                                                t.strong().color(crate::theme::ACCENT)
+                                            } else {
+                                                t
+                                            }
+                                        };
+                                        ui.label(cell(format!("#{}", row.rank)));
+                                        ui.label(cell(row.name.clone()));
+                                        ui.label(cell(format!("⭐ {}", row.score)));
+                                        ui.end_row();
+                                    }
+                                });
+                            ui.add_space(8.0);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_sized([120.0, 28.0], egui::Button::new("Play Again"))
+                                .clicked()
+                            {
+                                action.try_again = true;
+                            }
+                            if ui
+                                .add_sized([160.0, 28.0], egui::Button::new("Back to Menu"))
+                                .clicked()
+                            {
+                                *gave_up = true;
+                            }
+                        });
+                    });
+                });
+            });
+        return action;
+    }
+    let royale = world.mode == ModeKind::BattleRoyale;
+
     // Game over when no lives left and dead
     if let Some(n) = net {
         // Find me
@@ -36,16 +149,22 @@ pub fn show(
                         .show(ctx, |ui| {
                             ui_neon::neon_popup_frame(ui, NeonTheme::default()).show(ui, |ui| {
                                 ui.vertical_centered(|ui| {
-                                    ui.heading("Game Over");
-                                    ui.label("No lives left");
+                                    if royale {
+                                        ui.heading("Eliminated");
+                                        ui.label("Spectating until the round ends");
+                                    } else {
+                                        ui.heading("Game Over");
+                                        ui.label("No lives left");
+                                    }
                                     ui.add_space(8.0);
                                     ui.horizontal(|ui| {
-                                        if ui
-                                            .add_sized(
-                                                [120.0, 28.0],
-                                                egui::Button::new("Try Again"),
-                                            )
-                                            .clicked()
+                                        if !royale
+                                            && ui
+                                                .add_sized(
+                                                    [120.0, 28.0],
+                                                    egui::Button::new("Try Again"),
+                                                )
+                                                .clicked()
                                         {
                                             action.try_again = true;
                                         }
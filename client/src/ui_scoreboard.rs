@@ -1,21 +1,24 @@
-use crate::theme::ACCENT;
+use crate::theme::{team_palette, ACCENT};
 use eframe::egui::{
     self, Align, Area, Color32, Frame, Layout, Pos2, RichText, Rounding, ScrollArea, Stroke, Ui,
     Vec2,
 };
 
+use crate::net::{PlayerSnapshot, TeamScore};
 use crate::ui::RemoteWorld;
 
 // SidePanel version removed to avoid overlapping edge-cases — using overlay aligned to board instead.
 
 /// Overlay scoreboard positioned to the right of the given board rectangle.
+/// Returns the id of a bot the user asked to remove, if any.
 pub fn overlay_next_to(
     ctx: &egui::Context,
     bounds: egui::Rect,
     board_rect: egui::Rect,
     world: Option<&RemoteWorld>,
     me_id: Option<&str>,
-) {
+) -> Option<String> {
+    let mut remove_bot = None;
     let gap = 64.0; // distance between board and scoreboard
     let width = 320.0;
     // Match the board height exactly so they align visually
@@ -65,36 +68,32 @@ pub fn overlay_next_to(
                                 .show(ui, |ui| {
                                     if let Some(world) = world {
                                         // rank by score desc
-                                        let mut entries: Vec<_> = world
-                                            .players
-                                            .iter()
-                                            .map(|p| {
-                                                (
-                                                    p.id.clone(),
-                                                    p.name.clone(),
-                                                    p.score,
-                                                    p.alive,
-                                                    p.lives,
-                                                )
-                                            })
-                                            .collect();
-                                        entries.sort_by_key(|(_, _, score, _, _)| {
-                                            std::cmp::Reverse(*score)
-                                        });
+                                        let mut entries: Vec<&PlayerSnapshot> =
+                                            world.players.iter().collect();
+                                        entries.sort_by_key(|p| std::cmp::Reverse(p.score));
 
-                                        for (rank, (id, name, score, alive, lives)) in
-                                            entries.into_iter().enumerate()
-                                        {
-                                            player_row(
-                                                ui,
-                                                rank + 1,
-                                                &id,
-                                                &name,
-                                                score,
-                                                alive,
-                                                lives,
-                                                me_id,
-                                            );
+                                        if world.teams.is_empty() {
+                                            for (rank, p) in entries.into_iter().enumerate() {
+                                                if player_row(ui, rank + 1, p, me_id) {
+                                                    remove_bot = Some(p.id.clone());
+                                                }
+                                            }
+                                        } else {
+                                            // group by team, best team first
+                                            let mut teams = world.teams.clone();
+                                            teams.sort_by_key(|t| std::cmp::Reverse(t.score));
+                                            for t in &teams {
+                                                team_header(ui, t);
+                                                let members = entries
+                                                    .iter()
+                                                    .filter(|p| p.team == Some(t.team));
+                                                for (rank, p) in members.enumerate() {
+                                                    if player_row(ui, rank + 1, p, me_id) {
+                                                        remove_bot = Some(p.id.clone());
+                                                    }
+                                                }
+                                                ui.add_space(6.0);
+                                            }
                                         }
                                     } else {
                                         ui.label("Waiting for state…");
@@ -105,50 +104,113 @@ pub fn overlay_next_to(
                 },
             );
         });
+    remove_bot
 }
 
-fn player_row(
-    ui: &mut Ui,
-    rank: usize,
-    id: &str,
-    name: &str,
-    score: u32,
-    alive: bool,
-    lives: u32,
-    me_id: Option<&str>,
-) {
+fn team_header(ui: &mut Ui, t: &TeamScore) {
+    let pal = team_palette(t.team);
+    Frame {
+        inner_margin: egui::Margin::symmetric(10.0, 4.0),
+        rounding: Rounding::same(12.0),
+        stroke: Stroke::new(1.0, pal.head),
+        fill: pal.head.linear_multiply(0.20),
+        ..Default::default()
+    }
+    .show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(format!("Team {}", pal.name))
+                    .strong()
+                    .color(pal.head),
+            );
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                ui.monospace(format!("Σ {}", t.score));
+            });
+        });
+    });
+}
+
+/// Returns true when "Remove bot" was picked from the row's context menu.
+fn player_row(ui: &mut Ui, rank: usize, p: &PlayerSnapshot, me_id: Option<&str>) -> bool {
+    let mut remove = false;
     let row_fill = Color32::TRANSPARENT;
+    let stroke = p.team.map_or(ACCENT, |t| team_palette(t).head);
     Frame {
         inner_margin: egui::Margin::symmetric(10.0, 6.0),
         outer_margin: egui::Margin::symmetric(0.0, 6.0),
         rounding: Rounding::same(8.0),
-        stroke: Stroke::new(1.0, ACCENT),
+        stroke: Stroke::new(1.0, stroke),
         fill: row_fill,
         ..Default::default()
     }
     .show(ui, |ui| {
         ui.horizontal(|ui| {
             pill(ui, format!("#{rank}"));
-            let is_me = me_id.map(|m| m == id).unwrap_or(false);
-            let label = if !name.is_empty() {
-                name.to_string()
+            let is_me = me_id.map(|m| m == p.id).unwrap_or(false);
+            let label = if !p.name.is_empty() {
+                p.name.clone()
             } else {
-                id.chars().take(6).collect()
+                p.id.chars().take(6).collect()
             };
+            if p.bot {
+                ui.label("🤖").on_hover_text("Bot");
+            }
             if is_me {
                 ui.label(RichText::new(label).strong());
             } else {
                 ui.label(label);
             }
             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                pill(ui, format!("⭐ {}", score));
+                pill(ui, format!("⭐ {}", p.score));
                 ui.label(
-                    RichText::new(format!("{}{}", if alive { "❤" } else { "♡" }, lives))
+                    RichText::new(format!("{}{}", if p.alive { "❤" } else { "♡" }, p.lives))
                         .monospace(),
                 );
+                if let Some(ms) = p.ping_ms {
+                    ping_indicator(ui, ms);
+                }
             });
         });
+    })
+    .response
+    .context_menu(|ui| {
+        if p.bot && ui.button("Remove bot").clicked() {
+            remove = true;
+            ui.close_menu();
+        }
     });
+    remove
+}
+
+/// Signal-strength style bars, coloured from green (good) to red (laggy).
+fn ping_indicator(ui: &mut Ui, ms: u32) {
+    let (bars, color) = match ms {
+        0..=59 => (3, Color32::from_rgb(90, 220, 120)),
+        60..=119 => (2, Color32::from_rgb(255, 210, 60)),
+        120..=199 => (1, Color32::from_rgb(255, 150, 60)),
+        _ => (0, Color32::from_rgb(255, 90, 90)),
+    };
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(14.0, 12.0), egui::Sense::hover());
+    for i in 0..3 {
+        let h = 4.0 + 4.0 * i as f32;
+        let bar = egui::Rect::from_min_size(
+            Pos2::new(rect.left() + 5.0 * i as f32, rect.bottom() - h),
+            Vec2::new(3.0, h),
+        );
+        let fill = if i < bars {
+            color
+        } else {
+            color.linear_multiply(0.25)
+        };
+        ui.painter().rect_filled(bar, 1.0, fill);
+    }
+    ui.label(
+        RichText::new(format!("{ms}ms"))
+            .monospace()
+            .small()
+            .color(color),
+    )
+    .on_hover_text("Round trip to the server");
 }
 
 fn pill(ui: &mut Ui, text: String) {
@@ -0,0 +1,414 @@
+//! Operator actions on live rooms and players.
+//!
+//! `Admin` is the one place these operations live; the token-protected
+//! `/admin` HTTP API and the `serve` console are thin front ends over it.
+//!
+//! | Method | Path | Action |
+//! |---|---|---|
+//! | `GET` | `/admin/rooms` | list rooms and players |
+//! | `GET` | `/admin/rooms/{room}` | one room |
+//! | `DELETE` | `/admin/rooms/{room}` | close the room |
+//! | `PUT` | `/admin/rooms/{room}/rules` | replace its rules (JSON `RoomRules`), starting a new round |
+//! | `POST` | `/admin/rooms/{room}/pause`, `/resume` | hold or release ticks |
+//! | `POST` | `/admin/rooms/{room}/bots` | `{"tier": "greedy"}` adds a bot |
+//! | `DELETE` | `/admin/rooms/{room}/players/{id}` | kick a player or remove a bot |
+//! | `POST` | `/admin/broadcast` | `{"message": "...", "room": null}` to everyone or one room |
+//! | `POST` | `/admin/drain` | drain and shut down |
+
+use crate::bot::BotTier;
+use crate::model::{DisconnectReason, S2C};
+use crate::{builder, AppState, RoomRules};
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, net::SocketAddr, sync::atomic::Ordering};
+use tracing::info;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomInfo {
+    pub name: String,
+    pub rules: RoomRules,
+    pub started: bool,
+    pub paused: bool,
+    pub round_over: bool,
+    pub players: Vec<PlayerInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerInfo {
+    pub id: String,
+    pub name: String,
+    /// Where the player connected from; `None` for bots.
+    pub addr: Option<SocketAddr>,
+    pub bot: Option<BotTier>,
+    pub alive: bool,
+    pub score: u32,
+    pub lives: u32,
+    pub team: Option<u8>,
+    /// Smoothed round-trip time and its mean deviation; `None` for bots.
+    pub rtt_ms: Option<u32>,
+    pub jitter_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminError {
+    NoSuchRoom(String),
+    NoSuchPlayer(String),
+    /// The room can't take another bot.
+    RoomFull(String),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminError::NoSuchRoom(room) => write!(f, "no such room: {room}"),
+            AdminError::NoSuchPlayer(id) => write!(f, "no such player: {id}"),
+            AdminError::RoomFull(room) => write!(f, "room {room} has no space for another bot"),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+/// Handle for operator actions; cheap to clone. Get one from `ServerHandle::admin`.
+#[derive(Clone)]
+pub struct Admin {
+    state: AppState,
+}
+
+impl Admin {
+    pub(crate) fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Every open room, sorted by name.
+    pub fn rooms(&self) -> Vec<RoomInfo> {
+        let mut rooms: Vec<RoomInfo> = self
+            .state
+            .rooms
+            .iter()
+            .map(|r| room_info(r.key(), r.value()))
+            .collect();
+        rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        rooms
+    }
+
+    pub fn room(&self, room: &str) -> Result<RoomInfo, AdminError> {
+        let r = self
+            .state
+            .rooms
+            .get(room)
+            .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+        Ok(room_info(room, &r))
+    }
+
+    /// Disconnect a player with `Kicked`, or take a bot off the board.
+    pub fn kick(&self, room: &str, id: &str) -> Result<(), AdminError> {
+        let mut r = self
+            .state
+            .rooms
+            .get_mut(room)
+            .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+        let player = r
+            .players
+            .get(id)
+            .ok_or_else(|| AdminError::NoSuchPlayer(id.into()))?;
+        match &player.tx {
+            Some(tx) => {
+                let _ = tx.send(S2C::Disconnect {
+                    reason: DisconnectReason::Kicked,
+                });
+            }
+            None => {
+                r.remove_bot(id);
+            }
+        }
+        info!("admin kick: room={room} id={id}");
+        Ok(())
+    }
+
+    /// Put a server-side bot in the room; returns its id.
+    pub fn add_bot(&self, room: &str, tier: BotTier) -> Result<String, AdminError> {
+        let mut r = self
+            .state
+            .rooms
+            .get_mut(room)
+            .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+        let id = r
+            .add_bot(tier)
+            .map_err(|_| AdminError::RoomFull(room.into()))?;
+        r.send_all(&r.snapshot());
+        info!("admin add_bot: room={room} id={id} tier={tier:?}");
+        Ok(id)
+    }
+
+    /// Disconnect everyone in `room` with `RoomClosed` and drop it.
+    pub fn close_room(&self, room: &str) -> Result<(), AdminError> {
+        let (_, r) = self
+            .state
+            .rooms
+            .remove(room)
+            .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+        r.disconnect_all(DisconnectReason::RoomClosed);
+        info!("admin close: room={room}");
+        Ok(())
+    }
+
+    /// Replace the room's rules and start a fresh round under them. Players
+    /// get a new `Hello` so clients pick up the mode change.
+    pub fn set_rules(&self, room: &str, rules: RoomRules) -> Result<(), AdminError> {
+        let mut r = self
+            .state
+            .rooms
+            .get_mut(room)
+            .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+        let rules = RoomRules {
+            teams: rules.teams.clamp(2, 4),
+            ..rules
+        };
+        r.set_rules(rules);
+        for p in r.players.values() {
+            if let Some(tx) = &p.tx {
+                let _ = tx.send(S2C::Hello {
+                    player_id: p.id.clone(),
+                    grid: (r.grid_w, r.grid_h),
+                    tick_hz: self.state.tick_hz,
+                    mode: r.mode_kind(),
+                });
+            }
+        }
+        r.send_all(&r.snapshot());
+        info!("admin rules: room={room} rules={rules:?}");
+        Ok(())
+    }
+
+    /// Hold (or release) a room's ticks. Players are told either way.
+    pub fn set_paused(&self, room: &str, paused: bool) -> Result<(), AdminError> {
+        let mut r = self
+            .state
+            .rooms
+            .get_mut(room)
+            .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+        r.paused = paused;
+        let text = if paused {
+            "Game paused by the server admin"
+        } else {
+            "Game resumed"
+        };
+        r.send_all(&S2C::Announcement { text: text.into() });
+        info!("admin pause: room={room} paused={paused}");
+        Ok(())
+    }
+
+    /// Send an announcement to one room, or every room; returns how many players got it.
+    pub fn say(&self, room: Option<&str>, text: &str) -> Result<usize, AdminError> {
+        let msg = S2C::Announcement { text: text.into() };
+        let mut reached = 0;
+        match room {
+            Some(room) => {
+                let r = self
+                    .state
+                    .rooms
+                    .get(room)
+                    .ok_or_else(|| AdminError::NoSuchRoom(room.into()))?;
+                r.send_all(&msg);
+                reached += r.humans();
+            }
+            None => {
+                for r in self.state.rooms.iter() {
+                    r.send_all(&msg);
+                    reached += r.humans();
+                }
+            }
+        }
+        info!("admin say: room={room:?} text={text:?}");
+        Ok(reached)
+    }
+
+    /// Stop taking new rooms and joins, then shut down once rounds finish.
+    pub fn drain(&self) {
+        builder::start_drain(&self.state);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.state.draining.load(Ordering::SeqCst)
+    }
+}
+
+fn room_info(name: &str, r: &crate::room::Room) -> RoomInfo {
+    RoomInfo {
+        name: name.to_string(),
+        rules: r.rules(),
+        started: r.started,
+        paused: r.paused,
+        round_over: r.round_over,
+        players: r
+            .players
+            .values()
+            .map(|p| PlayerInfo {
+                id: p.id.clone(),
+                name: p._name.clone(),
+                addr: p.addr,
+                bot: p.bot,
+                alive: p.alive,
+                score: p.score,
+                lives: p.lives,
+                team: p.team,
+                rtt_ms: p.rtt.rtt_ms(),
+                jitter_ms: p.rtt.jitter_ms(),
+            })
+            .collect(),
+    }
+}
+
+/* === HTTP API === */
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/rooms", get(list_rooms))
+        .route("/rooms/:room", get(get_room).delete(close_room))
+        .route("/rooms/:room/rules", put(set_rules))
+        .route("/rooms/:room/pause", post(pause))
+        .route("/rooms/:room/resume", post(resume))
+        .route("/rooms/:room/bots", post(add_bot))
+        .route("/rooms/:room/players/:id", delete(kick))
+        .route("/broadcast", post(broadcast))
+        .route("/drain", post(drain))
+        .route_layer(middleware::from_fn_with_state(state, require_token))
+}
+
+async fn require_token(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match (given, state.admin_token.as_deref()) {
+        (Some(given), Some(token)) if same(given.as_bytes(), token.as_bytes()) => {
+            next.run(req).await
+        }
+        _ => (StatusCode::UNAUTHORIZED, "missing or wrong admin token").into_response(),
+    }
+}
+
+/// Compare without bailing at the first differing byte.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AdminError::NoSuchRoom(_) | AdminError::NoSuchPlayer(_) => StatusCode::NOT_FOUND,
+            AdminError::RoomFull(_) => StatusCode::CONFLICT,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+fn admin(state: AppState) -> Admin {
+    Admin::new(state)
+}
+
+async fn list_rooms(State(state): State<AppState>) -> Json<Vec<RoomInfo>> {
+    Json(admin(state).rooms())
+}
+
+async fn get_room(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+) -> Result<Json<RoomInfo>, AdminError> {
+    admin(state).room(&room).map(Json)
+}
+
+async fn close_room(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+) -> Result<StatusCode, AdminError> {
+    admin(state).close_room(&room)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_rules(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+    Json(rules): Json<RoomRules>,
+) -> Result<StatusCode, AdminError> {
+    admin(state).set_rules(&room, rules)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn pause(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+) -> Result<StatusCode, AdminError> {
+    admin(state).set_paused(&room, true)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn resume(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+) -> Result<StatusCode, AdminError> {
+    admin(state).set_paused(&room, false)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn kick(
+    State(state): State<AppState>,
+    Path((room, id)): Path<(String, String)>,
+) -> Result<StatusCode, AdminError> {
+    admin(state).kick(&room, &id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct NewBot {
+    #[serde(default)]
+    tier: BotTier,
+}
+
+#[derive(Serialize)]
+struct BotId {
+    id: String,
+}
+
+async fn add_bot(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+    Json(b): Json<NewBot>,
+) -> Result<(StatusCode, Json<BotId>), AdminError> {
+    let id = admin(state).add_bot(&room, b.tier)?;
+    Ok((StatusCode::CREATED, Json(BotId { id })))
+}
+
+#[derive(Deserialize)]
+struct Broadcast {
+    message: String,
+    /// Every room when absent.
+    room: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Reached {
+    reached: usize,
+}
+
+async fn broadcast(
+    State(state): State<AppState>,
+    Json(b): Json<Broadcast>,
+) -> Result<Json<Reached>, AdminError> {
+    let reached = admin(state).say(b.room.as_deref(), &b.message)?;
+    Ok(Json(Reached { reached }))
+}
+
+async fn drain(State(state): State<AppState>) -> StatusCode {
+    admin(state).drain();
+    StatusCode::ACCEPTED
+}
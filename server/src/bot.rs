@@ -0,0 +1,251 @@
+//! In-process bot players. A bot is a normal `Player` without a socket; each
+//! tick the room asks its tier for a direction and queues it like client input.
+
+use crate::model::{Arena, Cell, Dir};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum BotTier {
+    /// Wanders randomly but never steps into a wall or body if it can help it.
+    #[serde(rename = "random")]
+    RandomSafe,
+    /// Shortest path to the apple.
+    #[default]
+    #[serde(rename = "greedy")]
+    Greedy,
+    /// Chases the apple only when the move leaves enough room to survive.
+    #[serde(rename = "survivor")]
+    Survivor,
+}
+
+impl BotTier {
+    pub const ALL: [BotTier; 3] = [BotTier::RandomSafe, BotTier::Greedy, BotTier::Survivor];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BotTier::RandomSafe => "Rando",
+            BotTier::Greedy => "Gobbler",
+            BotTier::Survivor => "Coil",
+        }
+    }
+}
+
+/// Everything a bot gets to look at when picking its next move.
+pub struct BotView<'a> {
+    pub arena: Arena,
+    pub food: Cell,
+    /// Own body, head first.
+    pub me: &'a [Cell],
+    pub dir: Dir,
+    /// Bodies of every other live snake.
+    pub others: Vec<&'a [Cell]>,
+}
+
+const DIRS: [Dir; 4] = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+
+pub fn step_cell(c: Cell, d: Dir) -> Cell {
+    match d {
+        Dir::Up => Cell(c.0, c.1 - 1),
+        Dir::Down => Cell(c.0, c.1 + 1),
+        Dir::Left => Cell(c.0 - 1, c.1),
+        Dir::Right => Cell(c.0 + 1, c.1),
+    }
+}
+
+pub fn opposite(d: Dir) -> Dir {
+    match d {
+        Dir::Up => Dir::Down,
+        Dir::Down => Dir::Up,
+        Dir::Left => Dir::Right,
+        Dir::Right => Dir::Left,
+    }
+}
+
+impl BotView<'_> {
+    fn blocked(&self) -> HashSet<Cell> {
+        self.me
+            .iter()
+            .chain(self.others.iter().flat_map(|b| b.iter()))
+            .copied()
+            .collect()
+    }
+
+    /// Moves that don't hit a wall, the storm or a body next tick.
+    fn safe_moves(&self, blocked: &HashSet<Cell>) -> Vec<Dir> {
+        let Some(&head) = self.me.first() else {
+            return vec![];
+        };
+        DIRS.into_iter()
+            .filter(|&d| d != opposite(self.dir))
+            .filter(|&d| {
+                let c = step_cell(head, d);
+                self.arena.contains(c) && !blocked.contains(&c)
+            })
+            .collect()
+    }
+
+    /// First step of a shortest path from the head to `target`, if any.
+    fn path_to(&self, target: Cell, blocked: &HashSet<Cell>) -> Option<Dir> {
+        let head = *self.me.first()?;
+        let mut seen: HashSet<Cell> = HashSet::from([head]);
+        let mut queue: VecDeque<(Cell, Dir)> = VecDeque::new();
+        for d in self.safe_moves(blocked) {
+            let c = step_cell(head, d);
+            seen.insert(c);
+            queue.push_back((c, d));
+        }
+        while let Some((c, first)) = queue.pop_front() {
+            if c == target {
+                return Some(first);
+            }
+            for d in DIRS {
+                let n = step_cell(c, d);
+                if self.arena.contains(n) && !blocked.contains(&n) && seen.insert(n) {
+                    queue.push_back((n, first));
+                }
+            }
+        }
+        None
+    }
+
+    /// Number of free cells reachable from `from`, capped at `cap`.
+    fn room_from(&self, from: Cell, blocked: &HashSet<Cell>, cap: usize) -> usize {
+        let mut seen: HashSet<Cell> = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(c) = queue.pop_front() {
+            if seen.len() >= cap {
+                break;
+            }
+            for d in DIRS {
+                let n = step_cell(c, d);
+                if self.arena.contains(n) && !blocked.contains(&n) && seen.insert(n) {
+                    queue.push_back(n);
+                }
+            }
+        }
+        seen.len()
+    }
+}
+
+/// Pick this tick's direction for a bot of the given tier.
+pub fn choose(tier: BotTier, view: &BotView, rng: &mut impl Rng) -> Dir {
+    let blocked = view.blocked();
+    let safe = view.safe_moves(&blocked);
+    let random_safe = |rng: &mut dyn rand::RngCore| -> Dir {
+        // mostly keep going straight so the wandering looks deliberate
+        if safe.contains(&view.dir) && rng.gen_bool(0.7) {
+            return view.dir;
+        }
+        safe.choose(rng).copied().unwrap_or(view.dir)
+    };
+    match tier {
+        BotTier::RandomSafe => random_safe(rng),
+        BotTier::Greedy => view
+            .path_to(view.food, &blocked)
+            .unwrap_or_else(|| random_safe(rng)),
+        BotTier::Survivor => {
+            let Some(&head) = view.me.first() else {
+                return view.dir;
+            };
+            // enough room to fit our whole body twice over counts as safe
+            let need = view.me.len() * 2;
+            let room = |d: Dir| view.room_from(step_cell(head, d), &blocked, need);
+            if let Some(d) = view.path_to(view.food, &blocked) {
+                if room(d) >= need {
+                    return d;
+                }
+            }
+            safe.iter()
+                .copied()
+                .max_by_key(|&d| (room(d), d == view.dir))
+                .unwrap_or(view.dir)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn view<'a>(me: &'a [Cell], dir: Dir, others: Vec<&'a [Cell]>, food: Cell) -> BotView<'a> {
+        BotView {
+            arena: Arena::full(10, 10),
+            food,
+            me,
+            dir,
+            others,
+        }
+    }
+
+    /// Bots-only rooms in every mode: many ticks without panics or overlapping snakes.
+    #[test]
+    fn soak_rooms_with_bots() {
+        use crate::model::ModeKind;
+        use crate::room::{Room, RoomRules};
+
+        for mode in [
+            ModeKind::Classic,
+            ModeKind::BattleRoyale,
+            ModeKind::TimeAttack,
+            ModeKind::Team,
+        ] {
+            let rules = RoomRules {
+                mode,
+                ..Default::default()
+            };
+            let mut room = Room::new("soak", 22, 22, 10, rules);
+            for tier in BotTier::ALL.into_iter().cycle().take(6) {
+                room.add_bot(tier).unwrap();
+            }
+            room.start();
+            for _ in 0..500 {
+                room.step();
+                if room.round_over {
+                    room.start();
+                }
+                let mut seen = HashSet::new();
+                for p in room.players.values().filter(|p| p.alive) {
+                    for c in &p.snake {
+                        assert!(
+                            seen.insert(*c) || mode == ModeKind::Team,
+                            "{mode:?}: overlap"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_safe_avoids_walls() {
+        let me = [Cell(9, 5), Cell(8, 5), Cell(7, 5)];
+        let v = view(&me, Dir::Right, vec![], Cell(0, 0));
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let d = choose(BotTier::RandomSafe, &v, &mut rng);
+            assert!(matches!(d, Dir::Up | Dir::Down));
+        }
+    }
+
+    #[test]
+    fn greedy_heads_for_food() {
+        let me = [Cell(2, 2), Cell(1, 2), Cell(0, 2)];
+        let v = view(&me, Dir::Right, vec![], Cell(2, 7));
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(choose(BotTier::Greedy, &v, &mut rng), Dir::Down);
+    }
+
+    #[test]
+    fn survivor_refuses_a_dead_end() {
+        // food sits in a 1-cell pocket walled off by another snake
+        let wall = [Cell(2, 4), Cell(2, 3), Cell(3, 3), Cell(4, 3), Cell(4, 4)];
+        let me = [Cell(3, 5), Cell(2, 5)];
+        let v = view(&me, Dir::Right, vec![&wall], Cell(3, 4));
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(choose(BotTier::Greedy, &v, &mut rng), Dir::Up);
+        assert_ne!(choose(BotTier::Survivor, &v, &mut rng), Dir::Up);
+    }
+}
@@ -0,0 +1,333 @@
+use crate::admin::Admin;
+use crate::clock::{self, SharedClock};
+use crate::config::{Config, Limits};
+use crate::model::DisconnectReason;
+use crate::{disconnect_everyone, router, ticker, AppState, RoomRules, TICK_HZ};
+use dashmap::DashMap;
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+use tracing::info;
+
+type ShutdownFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Configures and starts an embedded server.
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), Box<dyn std::error::Error>> {
+/// let server = snake_server::ServerBuilder::new()
+///     .bind([127, 0, 0, 1].into())
+///     .port(0) // any free port
+///     .start()
+///     .await?;
+/// println!("ws://{}/ws", server.local_addr());
+/// server.stop().await
+/// # }
+/// ```
+pub struct ServerBuilder {
+    ip: IpAddr,
+    port: u16,
+    defaults: RoomRules,
+    tick_hz: u32,
+    limits: Limits,
+    manual_ticks: bool,
+    clock: SharedClock,
+    drain_timeout: Duration,
+    admin_token: Option<String>,
+    replay_dir: Option<PathBuf>,
+    shutdown: Option<ShutdownFuture>,
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self {
+            ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 8080,
+            defaults: RoomRules::default(),
+            tick_hz: TICK_HZ,
+            limits: Limits::default(),
+            manual_ticks: false,
+            clock: clock::real(),
+            drain_timeout: Duration::from_secs(180),
+            admin_token: None,
+            replay_dir: None,
+            shutdown: None,
+        }
+    }
+}
+
+impl ServerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Address, port, room defaults, tick rate and limits from a loaded config.
+    pub fn from_config(cfg: &Config) -> Self {
+        Self::new()
+            .bind(cfg.bind)
+            .port(cfg.port)
+            .room_defaults(cfg.room)
+            .tick_hz(cfg.tick_hz)
+            .limits(cfg.limits)
+            .drain_timeout(Duration::from_secs(cfg.drain_timeout_secs))
+            .admin_token(cfg.admin_token.clone())
+            .replay_dir(cfg.replay_dir.clone())
+    }
+
+    /// Address to listen on; `0.0.0.0` by default.
+    pub fn bind(mut self, ip: IpAddr) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    /// Port to listen on; `8080` by default, `0` picks a free one.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Rules for rooms created without any in the join request.
+    pub fn room_defaults(mut self, rules: RoomRules) -> Self {
+        self.defaults = rules;
+        self
+    }
+
+    /// Simulation ticks per second for every room.
+    pub fn tick_hz(mut self, hz: u32) -> Self {
+        self.tick_hz = hz.max(1);
+        self
+    }
+
+    /// Caps on rooms, players per room and name length.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Don't run the background ticker; rooms only advance on `ServerHandle::tick`.
+    /// Lets tests step the game deterministically instead of racing a 10 Hz timer.
+    pub fn manual_ticks(mut self) -> Self {
+        self.manual_ticks = true;
+        self
+    }
+
+    /// Time source for the ticker and every room, e.g. a `ManualClock` in tests.
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// How long a drain waits for running rounds before stopping anyway.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Serve the `/admin` API to requests bearing this token; off when `None`.
+    pub fn admin_token(mut self, token: Option<String>) -> Self {
+        self.admin_token = token.filter(|t| !t.is_empty());
+        self
+    }
+
+    /// Record every room's matches as JSON lines in `dir`, which must exist.
+    pub fn replay_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.replay_dir = dir;
+        self
+    }
+
+    /// Drain once `fut` completes, then stop; see `ServerHandle::drain`.
+    pub fn shutdown(mut self, fut: impl Future<Output = ()> + Send + 'static) -> Self {
+        self.shutdown = Some(Box::pin(fut));
+        self
+    }
+
+    /// Bind the listener and start serving in the background.
+    pub async fn start(self) -> Result<ServerHandle, Box<dyn std::error::Error>> {
+        let state = AppState {
+            rooms: Arc::new(DashMap::new()),
+            defaults: self.defaults,
+            tick_hz: self.tick_hz,
+            limits: self.limits,
+            clock: self.clock,
+            metrics: Default::default(),
+            draining: Default::default(),
+            drain: Default::default(),
+            admin_token: self.admin_token.map(Arc::from),
+            replay_dir: self.replay_dir.map(Arc::from),
+        };
+        let app = router(state.clone());
+
+        let listener = tokio::net::TcpListener::bind(SocketAddr::new(self.ip, self.port)).await?;
+        let addr = listener.local_addr()?;
+        info!("listening on {}", addr);
+
+        let running = Arc::new(AtomicBool::new(true));
+        if !self.manual_ticks {
+            tokio::spawn(ticker(state.clone(), self.tick_hz, running.clone()));
+        }
+
+        if let Some(external) = self.shutdown {
+            let state = state.clone();
+            tokio::spawn(async move {
+                external.await;
+                start_drain(&state);
+            });
+        }
+        let mut drained = tokio::spawn(drain_task(state.clone(), self.drain_timeout));
+
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        let shutdown_state = state.clone();
+        let shutdown_future = async move {
+            tokio::select! {
+                _ = stop_rx => drained.abort(),
+                _ = &mut drained => {}
+            }
+            info!("shutting down");
+            disconnect_everyone(&shutdown_state, DisconnectReason::ServerShutdown).await;
+        };
+
+        let task = tokio::spawn(async move {
+            let app = app.into_make_service_with_connect_info::<SocketAddr>();
+            let served = axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_future)
+                .await;
+            running.store(false, Ordering::SeqCst);
+            served
+        });
+
+        Ok(ServerHandle {
+            addr,
+            state,
+            stop: Some(stop_tx),
+            task,
+        })
+    }
+}
+
+pub(crate) fn start_drain(state: &AppState) {
+    if !state.draining.swap(true, Ordering::SeqCst) {
+        info!("draining: refusing new rooms and joins");
+        state.drain.notify_one();
+    }
+}
+
+/// Once triggered, wait until no room has a round in progress (or `timeout`).
+/// Modes without rounds (classic, team) never hold a drain up.
+async fn drain_task(state: AppState, timeout: Duration) {
+    state.drain.notified().await;
+    let busy = || state.rooms.iter().filter(|r| r.round_in_progress()).count();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut poll = tokio::time::interval(Duration::from_millis(100));
+    loop {
+        poll.tick().await;
+        let rounds = busy();
+        if rounds == 0 {
+            info!("drained");
+            return;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            info!("drain timed out with {rounds} round(s) still running");
+            return;
+        }
+    }
+}
+
+/// A running server started by `ServerBuilder`. Dropping it stops the server.
+pub struct ServerHandle {
+    addr: SocketAddr,
+    state: AppState,
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<std::io::Result<()>>,
+}
+
+impl ServerHandle {
+    /// The address actually bound, including the port picked for `port(0)`.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Advance every started room by one tick, whether or not it is due.
+    /// Meant for servers built with `manual_ticks`.
+    pub fn tick(&self) {
+        for mut room in self.state.rooms.iter_mut() {
+            if room.started && !room.paused {
+                room.step();
+            }
+        }
+    }
+
+    /// Stop taking new rooms and joins, let running rounds finish, then stop.
+    /// Returns at once; use `wait` to block until the server is down.
+    pub fn drain(&self) {
+        start_drain(&self.state);
+    }
+
+    /// Operator actions on this server's rooms and players.
+    pub fn admin(&self) -> Admin {
+        Admin::new(self.state.clone())
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.state.draining.load(Ordering::SeqCst)
+    }
+
+    /// Stop right away and wait until the server has.
+    pub async fn stop(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        self.wait().await
+    }
+
+    /// Wait for the server to stop (after a drain or `stop`).
+    pub async fn wait(self) -> Result<(), Box<dyn std::error::Error>> {
+        // keep `stop` alive so dropping it doesn't count as a shutdown request
+        let _stop = self.stop;
+        self.task.await??;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ephemeral_port_and_stop() {
+        let server = ServerBuilder::new()
+            .bind(Ipv4Addr::LOCALHOST.into())
+            .port(0)
+            .start()
+            .await
+            .unwrap();
+        let addr = server.local_addr();
+        assert_ne!(addr.port(), 0);
+        assert!(tokio::net::TcpStream::connect(addr).await.is_ok());
+        server.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shutdown_future_stops_the_server() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = ServerBuilder::new()
+            .bind(Ipv4Addr::LOCALHOST.into())
+            .port(0)
+            .shutdown(async move {
+                let _ = rx.await;
+            })
+            .start()
+            .await
+            .unwrap();
+        tx.send(()).unwrap();
+        server.wait().await.unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+//! Time source for room timing and the tick scheduler.
+//!
+//! Production uses `RealClock`. `ManualClock` only moves when told to, so
+//! tests, replays and batch simulations can run faster than real time and
+//! still exercise the timing logic.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Resolve once `now() >= deadline`.
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// Wall-clock time via `Instant::now` and tokio timers.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep_until(deadline.into()))
+    }
+}
+
+pub fn real() -> SharedClock {
+    Arc::new(RealClock)
+}
+
+/// A clock that stands still until `advance` is called.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: watch::Sender<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: watch::channel(Duration::ZERO).0,
+        }
+    }
+
+    /// Move time forward, waking anything sleeping past the new time.
+    pub fn advance(&self, by: Duration) {
+        self.elapsed.send_modify(|e| *e += by);
+    }
+
+    /// Time advanced so far.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.borrow()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let target = deadline.saturating_duration_since(self.start);
+        let mut rx = self.elapsed.subscribe();
+        Box::pin(async move {
+            // a dropped clock never advances again; just stop waiting
+            let _ = rx.wait_for(|e| *e >= target).await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::{Room, RoomRules};
+
+    #[test]
+    fn room_is_due_only_after_its_tick_elapses() {
+        let clock = Arc::new(ManualClock::new());
+        let mut room = Room::new("t", 22, 22, 10, RoomRules::default());
+        room.set_clock(clock.clone());
+        room.start();
+        assert!(!room.tick_due());
+
+        clock.advance(Duration::from_millis(60));
+        assert!(!room.tick_due());
+        clock.advance(Duration::from_millis(40));
+        assert!(room.tick_due());
+
+        room.step();
+        assert!(!room.tick_due());
+    }
+
+    #[tokio::test]
+    async fn sleepers_wake_when_time_passes_the_deadline() {
+        let clock = Arc::new(ManualClock::new());
+        let sleep = clock.sleep_until(clock.now() + Duration::from_secs(5));
+        let task = tokio::spawn(sleep);
+
+        clock.advance(Duration::from_secs(4));
+        tokio::task::yield_now().await;
+        assert!(!task.is_finished());
+
+        clock.advance(Duration::from_secs(1));
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("sleeper should wake")
+            .unwrap();
+    }
+}
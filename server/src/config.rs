@@ -0,0 +1,246 @@
+//! Server settings from a TOML/JSON file, command-line flags and environment.
+//!
+//! Later sources win: file, then CLI flags, then environment variables.
+
+use crate::room::RoomRules;
+use serde::{Deserialize, Serialize};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind: IpAddr,
+    pub port: u16,
+    pub tick_hz: u32,
+    /// Where match replays are kept; created at startup if set.
+    pub replay_dir: Option<PathBuf>,
+    /// How long a drain (SIGTERM / Ctrl-C) waits for running rounds.
+    pub drain_timeout_secs: u64,
+    /// Bearer token for the `/admin` HTTP API; the API is off when unset.
+    pub admin_token: Option<String>,
+    /// Rules for rooms whose creator didn't ask for any.
+    pub room: RoomRules,
+    pub limits: Limits,
+    pub log: LogConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 8080,
+            tick_hz: crate::TICK_HZ,
+            replay_dir: None,
+            drain_timeout_secs: 180,
+            admin_token: None,
+            room: RoomRules::default(),
+            limits: Limits::default(),
+            log: LogConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    pub max_rooms: usize,
+    /// Humans only; bots don't count.
+    pub max_players_per_room: usize,
+    /// Humans and bots together; bots are refused past this.
+    pub max_snakes_per_room: usize,
+    /// Longer names are cut to this many characters.
+    pub max_name_len: usize,
+    /// Drop connections that send nothing (not even a ping) for this long.
+    pub idle_timeout_secs: u64,
+    /// Client text frames longer than this are dropped as a violation.
+    pub max_frame_bytes: usize,
+    /// Violations (rate limits, oversized frames, streams of malformed ones) tolerated
+    /// per 10 seconds before the client is disconnected.
+    pub max_violations: u32,
+    /// Turns a player can queue ahead of the tick; extra presses are dropped.
+    pub input_queue_depth: usize,
+    /// Control messages (not snapshots) that may wait for a slow client.
+    pub max_outbound_queue: usize,
+    /// Snapshots in a row a client may miss before it is disconnected.
+    pub max_snapshot_lag: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_rooms: 64,
+            max_players_per_room: 16,
+            max_snakes_per_room: 24,
+            max_name_len: 16,
+            idle_timeout_secs: 120,
+            max_frame_bytes: 1024,
+            max_violations: 20,
+            input_queue_depth: 2,
+            max_outbound_queue: 256,
+            max_snapshot_lag: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Compact,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// `tracing` filter, e.g. `info` or `snake_server=debug`.
+    pub level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Pretty,
+            level: "info".into(),
+        }
+    }
+}
+
+impl Config {
+    /// Read a config file; `.json` files are parsed as JSON, anything else as TOML.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("reading {}: {e}", path.display()))?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+        Ok(parsed.map_err(|e| format!("parsing {}: {e}", path.display()))?)
+    }
+
+    /// Apply `SNAKE_*` (and `RUST_LOG`) overrides, looked up through `var`.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        fn parse<T: std::str::FromStr>(name: &str, v: String) -> Result<T, String> {
+            v.parse()
+                .map_err(|_| format!("{name}: invalid value {v:?}"))
+        }
+        if let Some(v) = var("SNAKE_BIND_ADDR") {
+            self.bind = parse("SNAKE_BIND_ADDR", v)?;
+        }
+        if let Some(v) = var("SNAKE_PORT") {
+            self.port = parse("SNAKE_PORT", v)?;
+        }
+        if let Some(v) = var("SNAKE_TICK_HZ") {
+            self.tick_hz = parse("SNAKE_TICK_HZ", v)?;
+        }
+        if let Some(v) = var("SNAKE_REPLAY_DIR") {
+            self.replay_dir = Some(v.into());
+        }
+        if let Some(v) = var("SNAKE_DRAIN_TIMEOUT_SECS") {
+            self.drain_timeout_secs = parse("SNAKE_DRAIN_TIMEOUT_SECS", v)?;
+        }
+        if let Some(v) = var("SNAKE_ADMIN_TOKEN") {
+            self.admin_token = Some(v);
+        }
+        if let Some(v) = var("SNAKE_MAX_ROOMS") {
+            self.limits.max_rooms = parse("SNAKE_MAX_ROOMS", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_PLAYERS_PER_ROOM") {
+            self.limits.max_players_per_room = parse("SNAKE_MAX_PLAYERS_PER_ROOM", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_SNAKES_PER_ROOM") {
+            self.limits.max_snakes_per_room = parse("SNAKE_MAX_SNAKES_PER_ROOM", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_NAME_LEN") {
+            self.limits.max_name_len = parse("SNAKE_MAX_NAME_LEN", v)?;
+        }
+        if let Some(v) = var("SNAKE_IDLE_TIMEOUT_SECS") {
+            self.limits.idle_timeout_secs = parse("SNAKE_IDLE_TIMEOUT_SECS", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_FRAME_BYTES") {
+            self.limits.max_frame_bytes = parse("SNAKE_MAX_FRAME_BYTES", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_VIOLATIONS") {
+            self.limits.max_violations = parse("SNAKE_MAX_VIOLATIONS", v)?;
+        }
+        if let Some(v) = var("SNAKE_INPUT_QUEUE_DEPTH") {
+            self.limits.input_queue_depth = parse("SNAKE_INPUT_QUEUE_DEPTH", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_OUTBOUND_QUEUE") {
+            self.limits.max_outbound_queue = parse("SNAKE_MAX_OUTBOUND_QUEUE", v)?;
+        }
+        if let Some(v) = var("SNAKE_MAX_SNAPSHOT_LAG") {
+            self.limits.max_snapshot_lag = parse("SNAKE_MAX_SNAPSHOT_LAG", v)?;
+        }
+        if let Some(v) = var("SNAKE_LOG_FORMAT") {
+            self.log.format = serde_json::from_value(serde_json::Value::String(v.clone()))
+                .map_err(|_| format!("SNAKE_LOG_FORMAT: invalid value {v:?}"))?;
+        }
+        if let Some(v) = var("RUST_LOG") {
+            self.log.level = v;
+        }
+        Ok(())
+    }
+
+    /// The effective settings as TOML, for printing at startup. Secrets are masked.
+    pub fn to_toml(&self) -> String {
+        let mut shown = self.clone();
+        if shown.admin_token.is_some() {
+            shown.admin_token = Some("********".into());
+        }
+        toml::to_string_pretty(&shown).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModeKind;
+
+    #[test]
+    fn toml_file_fills_in_defaults() {
+        let cfg: Config = toml::from_str(
+            r#"
+            port = 9000
+            [room]
+            mode = "royale"
+            [limits]
+            max_rooms = 4
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.port, 9000);
+        assert_eq!(cfg.room.mode, ModeKind::BattleRoyale);
+        assert_eq!(cfg.limits.max_rooms, 4);
+        assert_eq!(cfg.limits.max_name_len, Limits::default().max_name_len);
+        assert_eq!(cfg.tick_hz, crate::TICK_HZ);
+    }
+
+    #[test]
+    fn env_overrides_and_round_trip() {
+        let mut cfg = Config {
+            port: 9000,
+            ..Default::default()
+        };
+        cfg.apply_env(|name| match name {
+            "SNAKE_PORT" => Some("7000".into()),
+            "SNAKE_LOG_FORMAT" => Some("json".into()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(cfg.port, 7000);
+        assert_eq!(cfg.log.format, LogFormat::Json);
+
+        let again: Config = toml::from_str(&cfg.to_toml()).unwrap();
+        assert_eq!(again.port, 7000);
+        assert!(cfg
+            .apply_env(|name| (name == "SNAKE_PORT").then(|| "lots".into()))
+            .is_err());
+    }
+}
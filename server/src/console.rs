@@ -0,0 +1,426 @@
+//! Operator console on stdin for `serve`.
+//!
+//! Every command goes through `Admin`, the same operations as the `/admin`
+//! HTTP API. On a terminal, Tab completes commands, rooms, players, bot tiers
+//! and rule keys; piped input is read line by line.
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use snake_server::admin::{Admin, RoomInfo};
+use snake_server::bot::BotTier;
+use snake_server::RoomRules;
+
+const COMMANDS: [(&str, &str); 12] = [
+    ("rooms", "list rooms"),
+    ("players", "players <room>"),
+    ("kick", "kick <room> <player id or name>"),
+    ("say", "say <room|*> <message>"),
+    ("pause", "pause <room>"),
+    ("resume", "resume <room>"),
+    (
+        "rules",
+        "rules <room> [mode=.. teams=.. friendly_fire=.. fill_bots=..]",
+    ),
+    ("addbot", "addbot <room> [random|greedy|survivor]"),
+    ("close", "close <room>"),
+    ("shutdown", "drain and stop; again to stop now"),
+    ("help", "this list"),
+    ("quit", "leave the console; the server keeps running"),
+];
+
+const RULE_KEYS: [&str; 4] = ["mode=", "teams=", "friendly_fire=", "fill_bots="];
+const MODES: [&str; 4] = ["classic", "royale", "time_attack", "team"];
+const TIERS: [&str; 3] = ["random", "greedy", "survivor"];
+
+/// Read commands on a background thread until stdin closes or `quit`.
+pub fn spawn(admin: Admin) {
+    #[cfg(unix)]
+    terminal::save();
+    std::thread::spawn(move || {
+        if let Err(e) = run(admin) {
+            eprintln!("console: {e}");
+        }
+    });
+}
+
+/// Put the terminal back the way `spawn` found it, in case the process
+/// exits while a prompt has it in raw mode.
+pub fn restore_terminal() {
+    #[cfg(unix)]
+    terminal::restore();
+}
+
+fn run(admin: Admin) -> Result<(), ReadlineError> {
+    let mut editor: Editor<Complete, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(Complete {
+        admin: admin.clone(),
+    }));
+    loop {
+        let line = match editor.readline("snake> ") {
+            Ok(line) => line,
+            // the prompt owns the terminal, so Ctrl-C lands here instead of as a signal
+            Err(ReadlineError::Interrupted) => "shutdown".into(),
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        if line == "quit" || line == "exit" {
+            return Ok(());
+        }
+        match exec(&admin, line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{out}"),
+            Err(e) => println!("error: {e}"),
+        }
+    }
+}
+
+/// Run one command line; returns what to print.
+fn exec(admin: &Admin, line: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut words = line.split_whitespace();
+    let cmd = words.next().unwrap_or_default();
+    let mut arg = |what: &str| words.next().ok_or(format!("missing {what}; try `help`"));
+    match cmd {
+        "help" => Ok(COMMANDS
+            .iter()
+            .map(|(name, usage)| format!("  {name:<9} {usage}"))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        "rooms" => {
+            let rooms = admin.rooms();
+            if rooms.is_empty() {
+                return Ok("no rooms".into());
+            }
+            Ok(rooms.iter().map(room_line).collect::<Vec<_>>().join("\n"))
+        }
+        "players" => {
+            let room = admin.room(arg("room")?)?;
+            if room.players.is_empty() {
+                return Ok("no players".into());
+            }
+            Ok(room
+                .players
+                .iter()
+                .map(|p| {
+                    let from = match (p.addr, p.bot) {
+                        (Some(addr), _) => addr.to_string(),
+                        (None, Some(tier)) => format!("bot ({})", tier_name(tier)),
+                        (None, None) => "local".into(),
+                    };
+                    let state = if p.alive { "alive" } else { "dead" };
+                    let ping = match (p.rtt_ms, p.jitter_ms) {
+                        (Some(rtt), Some(jitter)) => format!(" ping {rtt}±{jitter}ms"),
+                        _ => String::new(),
+                    };
+                    format!(
+                        "  {:<8}  {:<16} {from:<21} score {:<4} lives {} {state}{ping}",
+                        short_id(&p.id),
+                        p.name,
+                        p.score,
+                        p.lives
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "kick" => {
+            let room = admin.room(arg("room")?)?;
+            let id = find_player(&room, arg("player")?)?;
+            admin.kick(&room.name, &id)?;
+            Ok(format!("kicked {}", short_id(&id)))
+        }
+        "say" => {
+            let room = arg("room or *")?;
+            let text = words.collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                return Err("missing message".into());
+            }
+            let reached = admin.say((room != "*").then_some(room), &text)?;
+            Ok(format!("sent to {reached} player(s)"))
+        }
+        "pause" | "resume" => {
+            admin.set_paused(arg("room")?, cmd == "pause")?;
+            Ok(String::new())
+        }
+        "rules" => {
+            let room = admin.room(arg("room")?)?;
+            let changes: Vec<&str> = words.collect();
+            if changes.is_empty() {
+                return Ok(format!("{:?}", room.rules));
+            }
+            let rules = parse_rules(room.rules, &changes)?;
+            admin.set_rules(&room.name, rules)?;
+            Ok(format!("{:?}; new round started", rules))
+        }
+        "addbot" => {
+            let room = arg("room")?;
+            let tier = match words.next() {
+                Some(tier) => parse_tier(tier)?,
+                None => BotTier::default(),
+            };
+            Ok(format!("added {}", admin.add_bot(room, tier)?))
+        }
+        "close" => {
+            admin.close_room(arg("room")?)?;
+            Ok(String::new())
+        }
+        "shutdown" => {
+            if admin.is_draining() {
+                restore_terminal();
+                std::process::exit(130);
+            }
+            admin.drain();
+            Ok("draining; `shutdown` again to stop now".into())
+        }
+        other => Err(format!("unknown command {other:?}; try `help`").into()),
+    }
+}
+
+fn room_line(r: &RoomInfo) -> String {
+    let bots = r.players.iter().filter(|p| p.bot.is_some()).count();
+    let state = if r.paused {
+        "paused"
+    } else if r.round_over {
+        "round over"
+    } else if r.started {
+        "playing"
+    } else {
+        "waiting"
+    };
+    format!(
+        "  {:<16} {:<12} {} players ({bots} bots)  {state}",
+        r.name,
+        format!("{:?}", r.rules.mode),
+        r.players.len()
+    )
+}
+
+/// Enough of a UUID to tell players apart; `kick` accepts any unique prefix.
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+/// Match by exact name, or by a unique id prefix.
+fn find_player(room: &RoomInfo, who: &str) -> Result<String, String> {
+    if let Some(p) = room
+        .players
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(who))
+    {
+        return Ok(p.id.clone());
+    }
+    let mut hits = room.players.iter().filter(|p| p.id.starts_with(who));
+    match (hits.next(), hits.next()) {
+        (Some(p), None) => Ok(p.id.clone()),
+        (Some(_), Some(_)) => Err(format!("{who:?} matches more than one player")),
+        (None, _) => Err(format!("no player {who:?} in {}", room.name)),
+    }
+}
+
+fn parse_tier(s: &str) -> Result<BotTier, String> {
+    serde_json::from_value(serde_json::Value::String(s.into()))
+        .map_err(|_| format!("unknown bot tier {s:?}; one of {}", TIERS.join(", ")))
+}
+
+/// Apply `key=value` changes on top of `rules`.
+fn parse_rules(mut rules: RoomRules, changes: &[&str]) -> Result<RoomRules, String> {
+    for change in changes {
+        let (key, value) = change
+            .split_once('=')
+            .ok_or(format!("expected key=value, got {change:?}"))?;
+        let bad = || format!("bad value for {key}: {value:?}");
+        match key {
+            "mode" => {
+                rules.mode = serde_json::from_value(serde_json::Value::String(value.into()))
+                    .map_err(|_| bad())?
+            }
+            "teams" => rules.teams = value.parse().map_err(|_| bad())?,
+            "friendly_fire" => rules.friendly_fire = value.parse().map_err(|_| bad())?,
+            "fill_bots" => rules.fill_bots = value.parse().map_err(|_| bad())?,
+            other => return Err(format!("unknown rule {other:?}")),
+        }
+    }
+    Ok(rules)
+}
+
+/// Candidates for the word under the cursor, and where that word starts.
+fn complete(rooms: &[RoomInfo], line: &str) -> (usize, Vec<String>) {
+    let start = line.rfind(' ').map_or(0, |i| i + 1);
+    let word = &line[start..];
+    let before: Vec<&str> = line[..start].split_whitespace().collect();
+    let room = || {
+        before
+            .get(1)
+            .and_then(|name| rooms.iter().find(|r| r.name == *name))
+    };
+    let mut options: Vec<String> = match (before.first().copied(), before.len()) {
+        (None, _) => COMMANDS.iter().map(|(c, _)| c.to_string()).collect(),
+        (Some("say"), 1) => std::iter::once("*".to_string())
+            .chain(rooms.iter().map(|r| r.name.clone()))
+            .collect(),
+        (Some("players" | "kick" | "pause" | "resume" | "rules" | "addbot" | "close"), 1) => {
+            rooms.iter().map(|r| r.name.clone()).collect()
+        }
+        (Some("kick"), 2) => room()
+            .map(|r| {
+                r.players
+                    .iter()
+                    .map(|p| short_id(&p.id).to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        (Some("addbot"), 2) => TIERS.iter().map(|t| t.to_string()).collect(),
+        (Some("rules"), n) if n >= 2 => match word.strip_prefix("mode=") {
+            Some(_) => MODES.iter().map(|m| format!("mode={m}")).collect(),
+            None => RULE_KEYS.iter().map(|k| k.to_string()).collect(),
+        },
+        _ => Vec::new(),
+    };
+    options.retain(|o| o.starts_with(word));
+    (start, options)
+}
+
+fn tier_name(tier: BotTier) -> String {
+    serde_json::to_value(tier)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+struct Complete {
+    admin: Admin,
+}
+
+impl Completer for Complete {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(&self.admin.rooms(), &line[..pos]))
+    }
+}
+
+impl Hinter for Complete {
+    type Hint = String;
+}
+
+impl Highlighter for Complete {}
+
+impl Validator for Complete {}
+
+impl Helper for Complete {}
+
+/// The prompt puts the terminal in raw mode while it waits for a line; keep
+/// the original settings so an exit mid-prompt doesn't leave it that way.
+#[cfg(unix)]
+mod terminal {
+    use nix::sys::termios::{tcgetattr, tcsetattr, SetArg, Termios};
+    use std::sync::{Mutex, OnceLock};
+
+    static SAVED: OnceLock<Mutex<Termios>> = OnceLock::new();
+
+    pub fn save() {
+        if let Ok(t) = tcgetattr(std::io::stdin()) {
+            let _ = SAVED.set(Mutex::new(t));
+        }
+    }
+
+    pub fn restore() {
+        if let Some(t) = SAVED.get() {
+            if let Ok(t) = t.lock() {
+                let _ = tcsetattr(std::io::stdin(), SetArg::TCSANOW, &t);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snake_server::admin::PlayerInfo;
+    use snake_server::model::ModeKind;
+
+    fn room(name: &str, ids: &[(&str, &str)]) -> RoomInfo {
+        RoomInfo {
+            name: name.into(),
+            rules: RoomRules::default(),
+            started: false,
+            paused: false,
+            round_over: false,
+            players: ids
+                .iter()
+                .map(|(id, name)| PlayerInfo {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    addr: None,
+                    bot: None,
+                    alive: true,
+                    score: 0,
+                    lives: 3,
+                    team: None,
+                    rtt_ms: None,
+                    jitter_ms: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn completes_commands_rooms_players_and_rules() {
+        let rooms = [
+            room("lan", &[("0a1b2c3d-0000", "Ann"), ("ffee0011-0000", "Bob")]),
+            room("lobby", &[]),
+        ];
+        assert_eq!(complete(&rooms, "pa"), (0, vec!["pause".to_string()]));
+        assert_eq!(
+            complete(&rooms, "kick l"),
+            (5, vec!["lan".to_string(), "lobby".to_string()])
+        );
+        assert_eq!(
+            complete(&rooms, "kick lan ff"),
+            (9, vec!["ffee0011".to_string()])
+        );
+        assert_eq!(complete(&rooms, "say ").1[0], "*");
+        assert_eq!(
+            complete(&rooms, "addbot lan s").1,
+            vec!["survivor".to_string()]
+        );
+        assert_eq!(
+            complete(&rooms, "rules lan teams=3 mode=r").1,
+            vec!["mode=royale".to_string()]
+        );
+    }
+
+    #[test]
+    fn players_match_by_name_or_id_prefix() {
+        let r = room("lan", &[("0a1b-1", "Ann"), ("0a1b-2", "Bob")]);
+        assert_eq!(find_player(&r, "bob").unwrap(), "0a1b-2");
+        assert_eq!(find_player(&r, "0a1b-1").unwrap(), "0a1b-1");
+        assert!(find_player(&r, "0a1b").is_err());
+        assert!(find_player(&r, "Cat").is_err());
+    }
+
+    #[test]
+    fn rule_changes_apply_on_top_of_the_current_rules() {
+        let rules = parse_rules(RoomRules::default(), &["mode=team", "teams=3"]).unwrap();
+        assert_eq!(rules.mode, ModeKind::Team);
+        assert_eq!(rules.teams, 3);
+        assert_eq!(rules.fill_bots, RoomRules::default().fill_bots);
+        assert!(parse_rules(rules, &["teams"]).is_err());
+        assert!(parse_rules(rules, &["speed=9"]).is_err());
+        assert!(parse_tier("greedy").is_ok() && parse_tier("smart").is_err());
+    }
+}
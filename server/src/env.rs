@@ -0,0 +1,375 @@
+//! In-process training environment over a `Room`.
+//!
+//! No sockets, no tokio and no clock: every `step` is exactly one tick, so a
+//! seeded run replays identically and runs as fast as the simulation allows.
+//!
+//! ```
+//! use snake_server::env::{EnvConfig, SnakeEnv};
+//! use snake_server::model::Dir;
+//!
+//! let mut env = SnakeEnv::new(EnvConfig::default());
+//! let obs = env.reset(7);
+//! assert_eq!(obs.len(), 1);
+//! let out = env.step(&[Dir::Up]);
+//! assert_eq!(out.rewards.len(), 1);
+//! ```
+
+use crate::bot::BotTier;
+use crate::model::{Cell, Dir, S2C};
+use crate::room::{Room, RoomRules};
+
+/// Added to an agent's reward on the tick it dies, on top of any score change.
+pub const DEATH_REWARD: f32 = -1.0;
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub grid_w: i32,
+    pub grid_h: i32,
+    /// Number of externally controlled snakes.
+    pub agents: usize,
+    /// Built-in bots to play against.
+    pub bots: Vec<BotTier>,
+    pub rules: RoomRules,
+    /// Only used to convert round clocks (time attack) into ticks.
+    pub tick_hz: u32,
+    /// Truncate the episode after this many ticks.
+    pub max_ticks: u64,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            grid_w: 22,
+            grid_h: 22,
+            agents: 1,
+            bots: vec![],
+            rules: RoomRules::default(),
+            tick_hz: 10,
+            max_ticks: 2000,
+        }
+    }
+}
+
+/// One agent's view of the board as a `[channel][y][x]` tensor.
+///
+/// The board is framed by a one-cell border of wall, so the edge shows up like
+/// any other obstacle: board cell `(x, y)` sits at `(x + 1, y + 1)` and the
+/// tensor is two cells wider and taller than the grid. Snake channels hold 1.0
+/// on the head and 0.5 on the rest of the body, so heading can be read off the
+/// observation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+impl Observation {
+    /// Cells a snake dies on entering: the border, plus the storm in battle royale.
+    pub const WALLS: usize = 0;
+    pub const OWN_BODY: usize = 1;
+    pub const ENEMY_BODIES: usize = 2;
+    pub const FOOD: usize = 3;
+    pub const CHANNELS: usize = 4;
+    /// Width of the wall border on each side.
+    pub const BORDER: usize = 1;
+
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0.0; Self::CHANNELS * width * height],
+        }
+    }
+
+    /// Value at tensor position `(x, y)`, border included.
+    pub fn get(&self, channel: usize, x: usize, y: usize) -> f32 {
+        self.data[(channel * self.height + y) * self.width + x]
+    }
+
+    /// Set board cell `c`, or a border cell for coordinates just off the board.
+    fn set(&mut self, channel: usize, c: Cell, v: f32) {
+        let b = Self::BORDER as i32;
+        let (x, y) = (c.0 + b, c.1 + b);
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            let i = (channel * self.height + y as usize) * self.width + x as usize;
+            self.data[i] = v;
+        }
+    }
+
+    fn paint_snake(&mut self, channel: usize, body: &[Cell]) {
+        for (i, c) in body.iter().enumerate() {
+            self.set(channel, *c, if i == 0 { 1.0 } else { 0.5 });
+        }
+    }
+}
+
+/// Extra per-step details that don't fit the observation/reward/done triple.
+#[derive(Debug, Clone)]
+pub struct StepInfo {
+    pub tick: u64,
+    /// Per agent: mode score after this step.
+    pub scores: Vec<u32>,
+    /// Per agent: ate food this step.
+    pub ate: Vec<bool>,
+    /// The mode ended the round.
+    pub round_over: bool,
+    /// Stopped by `max_ticks` rather than by the game.
+    pub truncated: bool,
+    pub winner: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub observations: Vec<Observation>,
+    pub rewards: Vec<f32>,
+    pub dones: Vec<bool>,
+    pub info: StepInfo,
+}
+
+/// Agents are "agent-0", "agent-1", … and get one life per episode: once an
+/// agent dies it stays done and off the board until the next `reset`.
+pub struct SnakeEnv {
+    cfg: EnvConfig,
+    room: Room,
+    agents: Vec<String>,
+    done: Vec<bool>,
+    scores: Vec<u32>,
+}
+
+impl SnakeEnv {
+    pub fn new(cfg: EnvConfig) -> Self {
+        let agents = (0..cfg.agents).map(|i| format!("agent-{i}")).collect();
+        let mut env = Self {
+            room: Room::new("env", cfg.grid_w, cfg.grid_h, cfg.tick_hz, cfg.rules),
+            agents,
+            done: vec![],
+            scores: vec![],
+            cfg,
+        };
+        env.reset(0);
+        env
+    }
+
+    pub fn agents(&self) -> &[String] {
+        &self.agents
+    }
+
+    /// Start a fresh episode; the same seed always yields the same episode
+    /// for the same actions.
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        let cfg = &self.cfg;
+        let mut room = Room::new("env", cfg.grid_w, cfg.grid_h, cfg.tick_hz, cfg.rules);
+        room.reseed(seed);
+        // the caller picked the line-up; no network limits apply here
+        room.max_snakes = usize::MAX;
+        for id in &self.agents {
+            room.insert_player(id.clone(), id.clone(), None, None);
+        }
+        for tier in &cfg.bots {
+            room.add_bot(*tier).expect("no snake limit");
+        }
+        room.reset_round();
+        room.start();
+        self.room = room;
+        self.done = vec![false; self.agents.len()];
+        self.scores = vec![0; self.agents.len()];
+        self.observations()
+    }
+
+    /// Apply one action per agent (in `agents()` order) and advance one tick.
+    /// Actions for finished agents are ignored; reversals keep the current heading.
+    pub fn step(&mut self, actions: &[Dir]) -> StepResult {
+        for ((id, done), d) in self.agents.iter().zip(&self.done).zip(actions) {
+            if !done {
+                self.room.queue_input(id, *d);
+            }
+        }
+        let tick = self.room.advance();
+        let truncated = !tick.finished && self.room.round_ticks >= self.cfg.max_ticks;
+
+        let mut rewards = vec![0.0; self.agents.len()];
+        let mut ate = vec![false; self.agents.len()];
+        for (i, id) in self.agents.iter().enumerate() {
+            if self.done[i] {
+                continue;
+            }
+            let score = self.room.players.get(id).map_or(0, |p| p.score);
+            rewards[i] = score as f32 - self.scores[i] as f32;
+            self.scores[i] = score;
+            ate[i] = tick.eaten.contains(id);
+            if tick.deaths.contains(id) {
+                rewards[i] += DEATH_REWARD;
+                self.done[i] = true;
+                // keep modes that respawn from bringing the agent back
+                if let Some(p) = self.room.players.get_mut(id) {
+                    p.alive = false;
+                    p.lives = 0;
+                    p.snake.clear();
+                }
+            }
+        }
+        if tick.finished || self.room.round_over || truncated {
+            self.done.iter_mut().for_each(|d| *d = true);
+        }
+
+        StepResult {
+            observations: self.observations(),
+            rewards,
+            dones: self.done.clone(),
+            info: StepInfo {
+                tick: self.room.round_ticks,
+                scores: self.scores.clone(),
+                ate,
+                round_over: self.room.round_over,
+                truncated,
+                winner: self.room.winner.clone(),
+            },
+        }
+    }
+
+    /// True once every agent is done.
+    pub fn is_over(&self) -> bool {
+        self.done.iter().all(|d| *d)
+    }
+
+    /// The same `State` message a networked client would see, for rendering or debugging.
+    pub fn snapshot(&self) -> S2C {
+        self.room.snapshot()
+    }
+
+    fn observations(&self) -> Vec<Observation> {
+        let b = Observation::BORDER as i32;
+        let (w, h) = (self.cfg.grid_w.max(0), self.cfg.grid_h.max(0));
+        let mut base = Observation::new((w + 2 * b) as usize, (h + 2 * b) as usize);
+        for y in -b..h + b {
+            for x in -b..w + b {
+                if !self.room.arena.contains(Cell(x, y)) {
+                    base.set(Observation::WALLS, Cell(x, y), 1.0);
+                }
+            }
+        }
+        base.set(Observation::FOOD, self.room.food, 1.0);
+
+        self.agents
+            .iter()
+            .map(|id| {
+                let mut obs = base.clone();
+                for p in self.room.players.values().filter(|p| p.alive) {
+                    let channel = if &p.id == id {
+                        Observation::OWN_BODY
+                    } else {
+                        Observation::ENEMY_BODIES
+                    };
+                    obs.paint_snake(channel, &p.snake);
+                }
+                obs
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rollout(seed: u64) -> Vec<(Vec<f32>, Vec<bool>)> {
+        let mut env = SnakeEnv::new(EnvConfig {
+            agents: 2,
+            bots: vec![BotTier::Greedy, BotTier::RandomSafe],
+            ..Default::default()
+        });
+        env.reset(seed);
+        let dirs = [Dir::Up, Dir::Right, Dir::Down, Dir::Right];
+        (0..200)
+            .map(|t| {
+                let out = env.step(&[dirs[t % 4], dirs[(t / 3) % 4]]);
+                (out.rewards, out.dones)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_episode() {
+        assert_eq!(rollout(42), rollout(42));
+    }
+
+    #[test]
+    fn observation_has_own_head_and_food() {
+        let mut env = SnakeEnv::new(EnvConfig::default());
+        let obs = env.reset(1).remove(0);
+        assert_eq!((obs.width, obs.height), (24, 24));
+        assert_eq!(obs.data.len(), Observation::CHANNELS * 24 * 24);
+        let snapshot = env.snapshot();
+        let S2C::State { food, players, .. } = snapshot else {
+            panic!("expected state");
+        };
+        let head = players[0].body[0];
+        assert_eq!(
+            obs.get(
+                Observation::OWN_BODY,
+                head.0 as usize + 1,
+                head.1 as usize + 1
+            ),
+            1.0
+        );
+        assert_eq!(
+            obs.get(Observation::FOOD, food.0 as usize + 1, food.1 as usize + 1),
+            1.0
+        );
+    }
+
+    #[test]
+    fn board_edge_is_a_wall() {
+        let obs = SnakeEnv::new(EnvConfig::default()).reset(1).remove(0);
+        for y in 0..24 {
+            for x in 0..24 {
+                let edge = x == 0 || y == 0 || x == 23 || y == 23;
+                let wall = obs.get(Observation::WALLS, x, y);
+                assert_eq!(wall, if edge { 1.0 } else { 0.0 }, "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "step rate is only meaningful in release")]
+    fn steps_thousands_of_times_per_second() {
+        let mut env = SnakeEnv::new(EnvConfig {
+            bots: vec![BotTier::Greedy],
+            ..Default::default()
+        });
+        let dirs = [Dir::Up, Dir::Right, Dir::Down, Dir::Left];
+        let started = std::time::Instant::now();
+        let (mut steps, mut seed) = (0, 0);
+        while steps < 20_000 {
+            env.reset(seed);
+            seed += 1;
+            while !env.is_over() && steps < 20_000 {
+                env.step(&[dirs[steps / 5 % 4]]);
+                steps += 1;
+            }
+        }
+        let rate = steps as f64 / started.elapsed().as_secs_f64();
+        // runs at well over 10k on a laptop; leave room for a busy CI box
+        assert!(rate > 2_000.0, "{rate:.0} steps/s");
+    }
+
+    #[test]
+    fn wall_death_ends_agent_episode() {
+        let mut env = SnakeEnv::new(EnvConfig::default());
+        env.reset(3);
+        let mut last = None;
+        for _ in 0..30 {
+            let out = env.step(&[Dir::Up]);
+            if out.dones[0] {
+                last = Some(out);
+                break;
+            }
+        }
+        let out = last.expect("agent should hit the top wall");
+        assert!(out.rewards[0] <= DEATH_REWARD);
+        assert!(env.is_over());
+        let own = &out.observations[0].data[24 * 24..2 * 24 * 24];
+        assert!(own.iter().all(|v| *v == 0.0));
+    }
+}
@@ -0,0 +1,137 @@
+//! Why a client request was refused.
+//!
+//! Most go back to the client as `S2C::Error` (see `is_answered`); the ones
+//! marked as violations also count toward cutting a flooding client off.
+
+use crate::model::{ErrorCode, S2C};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// Not JSON, or not a message we know; carries the parser's complaint.
+    Malformed(String),
+    /// A malformed frame past the allowance for mistakes: a flood, not a typo.
+    TooManyMalformed,
+    /// Text frame longer than `Limits::max_frame_bytes`.
+    TooLarge {
+        len: usize,
+        max: usize,
+    },
+    /// Over the budget for this message type.
+    RateLimited(&'static str),
+    /// Input arrived with the player's queue already full. Counted, but
+    /// not sent: mashing keys isn't abuse.
+    QueueFull,
+    /// Only the room owner may send this.
+    NotOwner(&'static str),
+    NoSuchBot(String),
+    /// No more bots: the room already holds `Limits::max_snakes_per_room`.
+    RoomFull {
+        max: usize,
+    },
+    /// The server is draining and won't begin new rounds.
+    Draining(&'static str),
+}
+
+impl ProtocolError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ProtocolError::Malformed(_) | ProtocolError::TooManyMalformed => ErrorCode::BadMessage,
+            ProtocolError::TooLarge { .. } => ErrorCode::TooLarge,
+            ProtocolError::RateLimited(_) => ErrorCode::RateLimited,
+            ProtocolError::QueueFull => ErrorCode::QueueFull,
+            ProtocolError::NotOwner(_) => ErrorCode::NotOwner,
+            ProtocolError::NoSuchBot(_) => ErrorCode::NoSuchBot,
+            ProtocolError::RoomFull { .. } => ErrorCode::RoomFull,
+            ProtocolError::Draining(_) => ErrorCode::Draining,
+        }
+    }
+
+    /// Type of the request this answers, when known.
+    pub fn reference(&self) -> Option<&'static str> {
+        match self {
+            ProtocolError::RateLimited(kind)
+            | ProtocolError::NotOwner(kind)
+            | ProtocolError::Draining(kind) => Some(kind),
+            ProtocolError::QueueFull => Some("input"),
+            ProtocolError::NoSuchBot(_) => Some("remove_bot"),
+            ProtocolError::RoomFull { .. } => Some("add_bot"),
+            ProtocolError::Malformed(_)
+            | ProtocolError::TooManyMalformed
+            | ProtocolError::TooLarge { .. } => None,
+        }
+    }
+
+    /// Abuse rather than a mistake: counts toward `Limits::max_violations`.
+    pub fn is_violation(&self) -> bool {
+        matches!(
+            self,
+            ProtocolError::TooLarge { .. }
+                | ProtocolError::RateLimited(_)
+                | ProtocolError::TooManyMalformed
+        )
+    }
+
+    /// Worth an `S2C::Error`: a dropped extra turn isn't, and neither is
+    /// garbage past its allowance, which would only cost us a reply per frame.
+    pub fn is_answered(&self) -> bool {
+        !matches!(
+            self,
+            ProtocolError::QueueFull | ProtocolError::TooManyMalformed
+        )
+    }
+
+    pub fn to_s2c(&self) -> S2C {
+        S2C::Error {
+            code: self.code(),
+            message: self.to_string(),
+            reference: self.reference().map(String::from),
+        }
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Malformed(e) => write!(f, "malformed message: {e}"),
+            ProtocolError::TooManyMalformed => write!(f, "too many malformed messages"),
+            ProtocolError::TooLarge { len, max } => {
+                write!(f, "message of {len} bytes is over the {max} byte limit")
+            }
+            ProtocolError::RateLimited(kind) => write!(f, "too many {kind} messages; slow down"),
+            ProtocolError::QueueFull => write!(f, "input queue is full; turn dropped"),
+            ProtocolError::NotOwner(kind) => write!(f, "only the room owner may send {kind}"),
+            ProtocolError::NoSuchBot(id) => write!(f, "no bot {id:?} in this room"),
+            ProtocolError::RoomFull { max } => write!(f, "room already has {max} snakes"),
+            ProtocolError::Draining(kind) => {
+                write!(f, "server is shutting down; {kind} ignored")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_go_out_with_code_message_and_ref() {
+        let json = serde_json::to_value(ProtocolError::NotOwner("add_bot").to_s2c()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "error",
+                "code": "not_owner",
+                "message": "only the room owner may send add_bot",
+                "ref": "add_bot",
+            })
+        );
+        assert!(!ProtocolError::Malformed("eof".into()).is_violation());
+        assert!(ProtocolError::TooLarge { len: 9, max: 8 }.is_violation());
+        assert!(!ProtocolError::QueueFull.is_violation());
+        assert!(ProtocolError::TooManyMalformed.is_violation());
+        assert!(!ProtocolError::TooManyMalformed.is_answered());
+    }
+}
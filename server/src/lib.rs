@@ -1,221 +1,415 @@
-mod model;
+pub mod admin;
+pub mod bot;
+mod builder;
+pub mod clock;
+pub mod config;
+pub mod env;
+pub mod error;
+pub mod local;
+mod metrics;
+mod mode;
+pub mod model;
+mod outbox;
+mod ratelimit;
+mod replay;
 mod room;
+mod rtt;
+pub mod tournament;
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Query, State,
+        ConnectInfo, Query, State,
     },
+    http::{header, StatusCode},
     response::IntoResponse,
     routing::get,
     Router,
 };
+pub use builder::{ServerBuilder, ServerHandle};
+use clock::SharedClock;
+use config::Limits;
 use dashmap::DashMap;
+use error::ProtocolError;
 use futures_util::{SinkExt, StreamExt};
+use metrics::Metrics;
 use model::*;
+use outbox::Outbox;
+use ratelimit::InboundLimiter;
 use room::Room;
+pub use room::RoomRules;
 use serde::Deserialize;
 use std::{
     net::SocketAddr,
+    path::Path,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::sync::oneshot;
-use tokio::time::interval;
+use tokio::sync::{oneshot, Notify};
 use tracing::*;
 use uuid::Uuid;
 
+const TICK_HZ: u32 = 10;
+/// Frames past this are refused by the WebSocket layer itself.
+const MAX_WS_MESSAGE: usize = 64 * 1024;
+
 #[derive(Clone)]
 struct AppState {
     rooms: Arc<DashMap<String, Room>>,
+    /// Rules for rooms whose creator didn't ask for any.
+    defaults: RoomRules,
+    tick_hz: u32,
+    limits: Limits,
+    clock: SharedClock,
+    metrics: Arc<Metrics>,
+    /// Set by a drain: no new rooms, joins or rounds.
+    draining: Arc<AtomicBool>,
+    /// Wakes the drain task; see `builder::start_drain`.
+    drain: Arc<Notify>,
+    /// Bearer token for `/admin`; the API is off without one.
+    admin_token: Option<Arc<str>>,
+    /// Rooms record their matches here when set.
+    replay_dir: Option<Arc<Path>>,
 }
 
 #[derive(Deserialize)]
 struct WsParams {
     room: Option<String>,
     name: Option<String>,
+    // Room rules; only used when this connection creates the room.
+    mode: Option<ModeKind>,
+    teams: Option<u8>,
+    ff: Option<bool>,
+    bots: Option<u8>,
+    /// Client's `PROTOCOL_VERSION`; older clients don't send one.
+    v: Option<u32>,
 }
 
+/// Run the server on `0.0.0.0:8080` until the process exits.
 pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt().with_env_filter("info").init();
-
-    let state = AppState {
-        rooms: Arc::new(DashMap::new()),
-    };
-    let app = Router::new()
-        .route("/ws", get(ws_handler))
-        .with_state(state.clone());
-
-    tokio::spawn(ticker(state.clone(), 10, Arc::new(AtomicBool::new(true))));
-
-    let addr: SocketAddr = "0.0.0.0:8080".parse().unwrap();
-    info!("listening on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
-    Ok(())
+    ServerBuilder::new().start().await?.wait().await
 }
 
+/// Run the server on `0.0.0.0:8080`; once `shutdown` fires, drain and stop.
 pub async fn run_with_shutdown(
     shutdown: oneshot::Receiver<()>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt().with_env_filter("info").init();
-
-    let state = AppState {
-        rooms: Arc::new(DashMap::new()),
-    };
-    let app = Router::new()
-        .route("/ws", get(ws_handler))
-        .with_state(state.clone());
-
-    let running = Arc::new(AtomicBool::new(true));
-    let running_for_ticker = running.clone();
-    tokio::spawn(ticker(state.clone(), 10, running_for_ticker));
-
-    // Flip running to false when shutdown signal arrives
-    let running_for_signal = running.clone();
-    tokio::spawn(async move {
-        let _ = shutdown.await;
-        running_for_signal.store(false, Ordering::SeqCst);
-    });
-
-    let addr: SocketAddr = "0.0.0.0:8080".parse().unwrap();
-    info!("listening on {}", addr);
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-
-    let shutdown_future = async move {
-        while running.load(Ordering::SeqCst) {
-            tokio::time::sleep(Duration::from_millis(50)).await;
-        }
-    };
+    ServerBuilder::new()
+        .shutdown(async move {
+            let _ = shutdown.await;
+        })
+        .start()
+        .await?
+        .wait()
+        .await
+}
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_future)
-        .await?;
-    Ok(())
+fn router(state: AppState) -> Router {
+    let mut app = Router::new();
+    if state.admin_token.is_some() {
+        app = app.nest("/admin", admin::router(state.clone()));
+    }
+    app.route("/ws", get(ws_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/readyz", get(readyz_handler))
+        .with_state(state)
 }
 
 async fn ticker(state: AppState, hz: u32, running: Arc<AtomicBool>) {
-    let mut tick = interval(Duration::from_millis((1000 / hz.max(1)) as u64));
+    let period = Duration::from_millis((1000 / hz.max(1)) as u64);
+    let mut next = state.clock.now() + period;
     while running.load(Ordering::SeqCst) {
-        tick.tick().await;
+        state.clock.sleep_until(next).await;
+        // after a stall, carry on from now rather than bursting through missed ticks
+        next = (next + period).max(state.clock.now());
+        let started = Instant::now();
         for mut room in state.rooms.iter_mut() {
             if room.tick_due() {
                 room.step();
             }
         }
+        if started.elapsed() > period {
+            Metrics::inc(&state.metrics.tick_overruns);
+        }
+    }
+}
+
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let rooms: Vec<_> = state.rooms.iter().collect();
+    let body = state
+        .metrics
+        .render(rooms.iter().map(|r| (r.key().as_str(), r.value())));
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// 503 while draining so load balancers stop sending new players.
+async fn readyz_handler(State(state): State<AppState>) -> impl IntoResponse {
+    if state.draining.load(Ordering::SeqCst) {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining")
+    } else {
+        (StatusCode::OK, "ready")
     }
 }
 
 async fn ws_handler(
     ws: WebSocketUpgrade,
     Query(q): Query<WsParams>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| client_conn(socket, q, state))
+    // hard cap well above max_frame_bytes; smaller overruns get a proper reason
+    ws.max_message_size(MAX_WS_MESSAGE)
+        .on_upgrade(move |socket| client_conn(socket, q, addr, state))
 }
 
-async fn client_conn(socket: WebSocket, q: WsParams, state: AppState) {
+async fn client_conn(socket: WebSocket, q: WsParams, addr: SocketAddr, state: AppState) {
     let room_name = q.room.unwrap_or_else(|| "lobby".into());
-    let player_name = q.name.unwrap_or_else(|| "Anon".into());
+    let player_name: String = q
+        .name
+        .unwrap_or_else(|| "Anon".into())
+        .chars()
+        .take(state.limits.max_name_len)
+        .collect();
     let player_id = Uuid::new_v4().to_string();
+    let defaults = state.defaults;
+    let rules = RoomRules {
+        mode: q.mode.unwrap_or(defaults.mode),
+        teams: q.teams.unwrap_or(defaults.teams).clamp(2, 4),
+        friendly_fire: q.ff.unwrap_or(defaults.friendly_fire),
+        fill_bots: q
+            .bots
+            .unwrap_or(defaults.fill_bots)
+            .min(u8::try_from(state.limits.max_snakes_per_room).unwrap_or(u8::MAX)),
+    };
 
     // channel from server → this client
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<S2C>();
+    let (tx, mut rx) = outbox::channel(
+        state.limits.max_outbound_queue,
+        state.limits.max_snapshot_lag,
+    );
+
+    if q.v.is_some_and(|v| v != PROTOCOL_VERSION) {
+        warn!("reject: room={room_name} reason=version {:?}", q.v);
+        return refuse(socket, &state, DisconnectReason::VersionMismatch).await;
+    }
+    if state.draining.load(Ordering::SeqCst) {
+        warn!("reject: room={room_name} reason=draining");
+        return refuse(socket, &state, DisconnectReason::ServerShutdown).await;
+    }
+    if !state.rooms.contains_key(&room_name) && state.rooms.len() >= state.limits.max_rooms {
+        warn!(
+            "reject: room={room_name} reason=room limit ({})",
+            state.limits.max_rooms
+        );
+        return refuse(socket, &state, DisconnectReason::ServerFull).await;
+    }
 
     // --- limit the mutable guard scope so the ticker can borrow later ---
-    {
+    let full = {
         // ensure room (mutable!)
-        let mut room_entry = state
-            .rooms
-            .entry(room_name.clone())
-            .or_insert_with(|| Room::new(&room_name, 22, 22, 10));
-
-        // register the player
-        room_entry.add_player(player_id.clone(), player_name, tx.clone());
-        info!("join: room={room_name} id={player_id}");
-
-        // send Hello
-        let _ = tx.send(S2C::Hello {
-            player_id: player_id.clone(),
-            grid: (room_entry.grid_w, room_entry.grid_h),
-            tick_hz: 10,
+        let mut room_entry = state.rooms.entry(room_name.clone()).or_insert_with(|| {
+            let mut room = Room::new(&room_name, 22, 22, state.tick_hz, rules);
+            room.set_clock(state.clock.clone());
+            room.input_queue_depth = state.limits.input_queue_depth.max(1);
+            room.max_snakes = state.limits.max_snakes_per_room;
+            if let Some(dir) = &state.replay_dir {
+                // only starts the writer thread; the file is opened there
+                match replay::ReplayWriter::spawn(dir, &room_name) {
+                    Ok(w) => room.replay = Some(w),
+                    Err(e) => warn!("replay: room={room_name} not recorded: {e}"),
+                }
+            }
+            room
         });
 
-        // send immediate State so the client sees itself right away
-        let snap = room_entry.snapshot();
-        let players_len = match &snap {
-            S2C::State { players, .. } => players.len(),
-            _ => 0,
-        };
-        info!("snapshot_on_join: players={players_len}");
-        let _ = tx.send(snap);
-
+        let full = room_entry.humans() >= state.limits.max_players_per_room;
+        if !full {
+            join_room(
+                &mut room_entry,
+                &room_name,
+                &player_id,
+                player_name,
+                Some(addr),
+                state.tick_hz,
+                &tx,
+            );
+        }
+        full
         // room_entry guard DROPS here
+    };
+    if full {
+        warn!("reject: room={room_name} reason=room full");
+        return refuse(socket, &state, DisconnectReason::RoomFull).await;
     }
+    Metrics::inc(&state.metrics.connections_opened);
     // --------------------------------------------------------------------
 
     // split socket
     let (mut sender, mut receiver) = socket.split();
 
-    // outbound pump: server → client
-    let outbound = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if sender
-                .send(Message::Text(serde_json::to_string(&msg).unwrap()))
-                .await
-                .is_err()
-            {
+    // outbound pump: server → client, plus a WebSocket ping every
+    // PING_INTERVAL carrying its send time; the pong comes back below
+    let epoch = tokio::time::Instant::now();
+    let metrics = state.metrics.clone();
+    let mut outbound = tokio::spawn(async move {
+        let ping =
+            move || Message::Ping((epoch.elapsed().as_micros() as u64).to_be_bytes().to_vec());
+        // first one straight away, so there's a ping to show from the start
+        if sender.send(ping()).await.is_err() {
+            return;
+        }
+        let mut ping_timer =
+            tokio::time::interval_at(epoch + rtt::PING_INTERVAL, rtt::PING_INTERVAL);
+        loop {
+            let msg = tokio::select! {
+                msg = rx.recv() => msg,
+                _ = ping_timer.tick() => {
+                    if sender.send(ping()).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let text = match serde_json::to_string(&msg) {
+                Ok(text) => text,
+                Err(e) => {
+                    error!("serialize: {e} msg={msg:?}");
+                    Metrics::inc(&metrics.serialize_failures);
+                    continue;
+                }
+            };
+            if matches!(msg, S2C::State { .. }) {
+                metrics
+                    .snapshot_bytes_sent
+                    .fetch_add(text.len() as u64, Ordering::Relaxed);
+            }
+            if sender.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+            // the reason is out; hang up
+            if matches!(msg, S2C::Disconnect { .. }) {
+                let _ = sender.send(Message::Close(None)).await;
                 break;
             }
         }
     });
 
-    // inbound loop: client → server
-    while let Some(Ok(msg)) = receiver.next().await {
+    // inbound loop: client → server, until either side hangs up
+    let idle = Duration::from_secs(state.limits.idle_timeout_secs.max(1));
+    let mut limiter = InboundLimiter::new(&state.limits, state.clock.now());
+    let mut idle_at = epoch + idle;
+    let mut cut_off = false;
+    loop {
+        let msg = tokio::select! {
+            _ = &mut outbound => {
+                // we've sent Close; read up to the client's, since closing on
+                // unread data resets the connection and can lose the reason
+                let _ = tokio::time::timeout(Duration::from_secs(1), async {
+                    while let Some(Ok(msg)) = receiver.next().await {
+                        if matches!(msg, Message::Close(_)) {
+                            break;
+                        }
+                    }
+                })
+                .await;
+                break;
+            }
+            _ = tx.cut_off() => None,
+            msg = tokio::time::timeout_at(idle_at, receiver.next()) => Some(msg),
+        };
+        let Some(msg) = msg else {
+            warn!("slow client: room={room_name} id={player_id} addr={addr}");
+            Metrics::inc(&state.metrics.slow_client_disconnects);
+            // the reason is queued; give it a moment in case the socket drains
+            let _ = tokio::time::timeout(Duration::from_secs(1), &mut outbound).await;
+            break;
+        };
+        let msg = match msg {
+            Ok(Some(Ok(msg))) => msg,
+            Ok(_) => break,
+            Err(_) => {
+                idle_at += idle;
+                info!("idle: room={room_name} id={player_id}");
+                let _ = tx.send(S2C::Disconnect {
+                    reason: DisconnectReason::IdleTimeout,
+                });
+                continue;
+            }
+        };
+        // pongs come from the client's WebSocket layer, not the player
+        if !matches!(msg, Message::Pong(_)) {
+            idle_at = tokio::time::Instant::now() + idle;
+        }
         match msg {
+            // already told to go; wait for the pump to hang up
+            Message::Text(_) if cut_off => {}
             Message::Text(txt) => {
-                if let Ok(c2s) = serde_json::from_str::<C2S>(&txt) {
-                    match c2s {
-                        C2S::Join { .. } => {}
-                        C2S::Input { dir } => {
-                            // short-lived mutable borrow; fine for the ticker
-                            if let Some(mut room) = state.rooms.get_mut(&room_name) {
-                                room.queue_input(&player_id, dir);
-                            }
-                        }
-                        C2S::Start => {
-                            if let Some(mut room) = state.rooms.get_mut(&room_name) {
-                                info!("start: room={room_name}");
-                                room.started = true;
-                                // optional: snapshot broadcast so clients update started flag immediately
-                                let _ = tx.send(room.snapshot());
-                            }
-                        }
-                        C2S::Respawn => {
-                            if let Some(mut room) = state.rooms.get_mut(&room_name) {
-                                room.respawn_player(&player_id);
-                                // send an immediate snapshot so the client sees the new state
-                                let _ = tx.send(room.snapshot());
-                            }
-                        }
-                        C2S::Restart => {
-                            if let Some(mut room) = state.rooms.get_mut(&room_name) {
-                                if let Some(p) = room.players.get_mut(&player_id) {
-                                    info!("restart: room={room_name} id={player_id}");
-                                    p.lives = 3;
+                let now = state.clock.now();
+                let verdict = if txt.len() > state.limits.max_frame_bytes {
+                    Err(ProtocolError::TooLarge {
+                        len: txt.len(),
+                        max: state.limits.max_frame_bytes,
+                    })
+                } else {
+                    match serde_json::from_str::<C2S>(&txt) {
+                        Err(e) => Err(limiter.malformed(e.to_string(), now)),
+                        Ok(c2s) => {
+                            state.metrics.received(&c2s);
+                            limiter.allow(&c2s, now).and_then(|()| {
+                                if state.draining.load(Ordering::SeqCst)
+                                    && matches!(c2s, C2S::Start | C2S::Restart)
+                                {
+                                    // let the current round end, but don't begin another
+                                    return Err(ProtocolError::Draining(c2s.kind()));
                                 }
-                                room.respawn_player(&player_id);
-                                let _ = tx.send(room.snapshot());
-                            }
-                        }
-                        C2S::Ping { t } => {
-                            let _ = tx.send(S2C::Pong { t });
+                                match state.rooms.get_mut(&room_name) {
+                                    // short-lived mutable borrow; fine for the ticker
+                                    Some(mut room) => {
+                                        handle_command(&mut room, &room_name, &player_id, c2s, &tx)
+                                    }
+                                    None => Ok(()),
+                                }
+                            })
                         }
                     }
+                };
+                let Err(e) = verdict else { continue };
+                state.metrics.protocol_error(&e);
+                debug!("refused: room={room_name} id={player_id} {e}");
+                if e.is_answered() {
+                    let _ = tx.send(e.to_s2c());
+                }
+                if e.is_violation() {
+                    if limiter.violations == 0 {
+                        warn!("violation: room={room_name} id={player_id} {e}");
+                    }
+                    if limiter.strike(now) {
+                        warn!(
+                            "flood: room={room_name} id={player_id} addr={addr} violations={}",
+                            limiter.violations
+                        );
+                        Metrics::inc(&state.metrics.flood_disconnects);
+                        let _ = tx.send(S2C::Disconnect {
+                            reason: DisconnectReason::RateLimited,
+                        });
+                        cut_off = true;
+                    }
+                }
+            }
+            Message::Pong(payload) => {
+                let Ok(sent) = <[u8; 8]>::try_from(payload.as_slice()) else {
+                    continue;
+                };
+                let sent = Duration::from_micros(u64::from_be_bytes(sent));
+                let rtt = epoch.elapsed().saturating_sub(sent);
+                if let Some(mut room) = state.rooms.get_mut(&room_name) {
+                    if let Some(p) = room.players.get_mut(&player_id) {
+                        p.rtt.sample(rtt);
+                    }
                 }
             }
             Message::Close(_) => break,
@@ -224,6 +418,7 @@ async fn client_conn(socket: WebSocket, q: WsParams, state: AppState) {
     }
 
     // cleanup
+    Metrics::inc(&state.metrics.connections_closed);
     if let Some(mut room) = state.rooms.get_mut(&room_name) {
         room.remove_player(&player_id);
         info!(
@@ -231,5 +426,123 @@ async fn client_conn(socket: WebSocket, q: WsParams, state: AppState) {
             room.players.len()
         );
     }
+    // last human out closes the room so it stops counting against the limit
+    if state
+        .rooms
+        .remove_if(&room_name, |_, room| room.humans() == 0)
+        .is_some()
+    {
+        info!("close: room={room_name}");
+    }
     outbound.abort();
 }
+
+/// Tell a client why it's being turned away, then close.
+async fn refuse(mut socket: WebSocket, state: &AppState, reason: DisconnectReason) {
+    Metrics::inc(&state.metrics.connections_rejected);
+    if let Ok(text) = serde_json::to_string(&S2C::Disconnect { reason }) {
+        let _ = socket.send(Message::Text(text)).await;
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// Tell every connected player why they're being dropped and give their
+/// connections a moment to deliver it and leave.
+async fn disconnect_everyone(state: &AppState, reason: DisconnectReason) {
+    for room in state.rooms.iter() {
+        room.disconnect_all(reason);
+    }
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while !state.rooms.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Seat a new player and greet them with `Hello` plus an immediate `State`.
+fn join_room(
+    room: &mut Room,
+    room_name: &str,
+    player_id: &str,
+    player_name: String,
+    addr: Option<SocketAddr>,
+    tick_hz: u32,
+    tx: &Outbox,
+) {
+    // register the player
+    room.add_player(player_id.to_string(), player_name, tx.clone());
+    if let Some(p) = room.players.get_mut(player_id) {
+        p.addr = addr;
+    }
+    info!("join: room={room_name} id={player_id} addr={addr:?}");
+
+    // send Hello
+    let _ = tx.send(S2C::Hello {
+        player_id: player_id.to_string(),
+        grid: (room.grid_w, room.grid_h),
+        tick_hz,
+        mode: room.mode_kind(),
+    });
+
+    // send immediate State so the client sees itself right away
+    let snap = room.snapshot();
+    let players_len = match &snap {
+        S2C::State { players, .. } => players.len(),
+        _ => 0,
+    };
+    info!("snapshot_on_join: players={players_len}");
+    let _ = tx.send(snap);
+}
+
+/// Apply one client command to its room. Shared by WebSocket and in-process players.
+fn handle_command(
+    room: &mut Room,
+    room_name: &str,
+    player_id: &str,
+    c2s: C2S,
+    tx: &Outbox,
+) -> Result<(), ProtocolError> {
+    let kind = c2s.kind();
+    match c2s {
+        C2S::Join { .. } => {}
+        C2S::Input { dir } => {
+            if !room.queue_input(player_id, dir) {
+                return Err(ProtocolError::QueueFull);
+            }
+        }
+        C2S::Start => {
+            info!("start: room={room_name}");
+            room.start();
+            // optional: snapshot broadcast so clients update started flag immediately
+            let _ = tx.send(room.snapshot());
+        }
+        C2S::Respawn => {
+            room.respawn_player(player_id);
+            // send an immediate snapshot so the client sees the new state
+            let _ = tx.send(room.snapshot());
+        }
+        C2S::Restart => {
+            info!("restart: room={room_name} id={player_id}");
+            room.restart_player(player_id);
+            let _ = tx.send(room.snapshot());
+        }
+        C2S::AddBot { .. } | C2S::RemoveBot { .. } if room.owner.as_deref() != Some(player_id) => {
+            return Err(ProtocolError::NotOwner(kind));
+        }
+        C2S::AddBot { tier } => {
+            let bot_id = room.add_bot(tier)?;
+            info!("add_bot: room={room_name} id={bot_id} tier={tier:?}");
+            let _ = tx.send(room.snapshot());
+        }
+        C2S::RemoveBot { id } => {
+            if !room.remove_bot(&id) {
+                return Err(ProtocolError::NoSuchBot(id));
+            }
+            info!("remove_bot: room={room_name} id={id}");
+            let _ = tx.send(room.snapshot());
+        }
+        C2S::Ping { t } => {
+            let _ = tx.send(S2C::Pong { t });
+        }
+    }
+    Ok(())
+}
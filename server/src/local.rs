@@ -0,0 +1,85 @@
+//! A single room simulated in-process for offline play.
+//!
+//! Speaks the same `C2S`/`S2C` messages as a WebSocket connection, but over
+//! plain channels, so a client can swap it in for the network without
+//! binding any port.
+
+use crate::model::{C2S, S2C};
+use crate::room::{Room, RoomRules};
+use crate::{handle_command, join_room, outbox, TICK_HZ};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const ROOM_NAME: &str = "offline";
+const PLAYER_ID: &str = "local";
+
+/// Start an offline game for `name` on a background thread.
+///
+/// Commands sent on the returned sender drive the game; everything the server
+/// would send arrives on the returned receiver, starting with `Hello`. The
+/// thread stops once the command sender is dropped.
+pub fn spawn(
+    name: String,
+    rules: RoomRules,
+) -> (mpsc::UnboundedSender<C2S>, mpsc::UnboundedReceiver<S2C>) {
+    let (tx_cmd, mut rx_cmd) = mpsc::unbounded_channel::<C2S>();
+    let (tx_out, rx) = mpsc::unbounded_channel::<S2C>();
+    // the room speaks to an outbox; forward it as the game loop goes round
+    let (tx, mut outbox) = outbox::channel(usize::MAX, u32::MAX);
+
+    std::thread::spawn(move || {
+        let mut room = Room::new(ROOM_NAME, 22, 22, TICK_HZ, rules);
+        join_room(&mut room, ROOM_NAME, PLAYER_ID, name, None, TICK_HZ, &tx);
+        loop {
+            loop {
+                match rx_cmd.try_recv() {
+                    Ok(c2s) => {
+                        let _ = handle_command(&mut room, ROOM_NAME, PLAYER_ID, c2s, &tx);
+                    }
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => return,
+                }
+            }
+            if room.tick_due() {
+                room.step();
+            }
+            while let Some(msg) = outbox.try_recv() {
+                if tx_out.send(msg).is_err() {
+                    return;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    });
+
+    (tx_cmd, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_game_greets_and_ticks() {
+        let (tx, mut rx) = spawn(
+            "Solo".into(),
+            RoomRules {
+                fill_bots: 3,
+                ..Default::default()
+            },
+        );
+        let Some(S2C::Hello { player_id, .. }) = rx.blocking_recv() else {
+            panic!("expected hello first");
+        };
+        assert_eq!(player_id, PLAYER_ID);
+
+        tx.send(C2S::Start).unwrap();
+        let ticked = std::iter::from_fn(|| rx.blocking_recv())
+            .take(50)
+            .any(|msg| matches!(msg, S2C::State { seq, started: true, ref players, .. } if seq > 0 && players.len() == 3));
+        assert!(
+            ticked,
+            "room should tick with the local player and two bots"
+        );
+    }
+}
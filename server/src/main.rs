@@ -1,19 +1,179 @@
+#[cfg(feature = "console")]
+mod console;
+/// Built without the `console` feature: no prompt, nothing to restore.
+#[cfg(not(feature = "console"))]
+mod console {
+    pub fn spawn(_admin: snake_server::admin::Admin) {}
+    pub fn restore_terminal() {}
+}
+
+use snake_server::config::{Config, LogFormat};
 use std::sync::Once;
 
 static TRACING_INIT: Once = Once::new();
 
-fn init_tracing() {
+fn init_tracing(log: &snake_server::config::LogConfig) {
     TRACING_INIT.call_once(|| {
-        tracing_subscriber::fmt()
-            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-            .init();
+        let filter = tracing_subscriber::EnvFilter::try_new(&log.level)
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+        let fmt = tracing_subscriber::fmt().with_env_filter(filter);
+        match log.format {
+            LogFormat::Pretty => fmt.init(),
+            LogFormat::Compact => fmt.compact().init(),
+            LogFormat::Json => fmt.json().init(),
+        }
     });
 }
 
+/// `serve [--bind IP] [--port N] [--config FILE] [--no-console]`; file < flags < environment.
+async fn serve(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config_path: Option<std::path::PathBuf> = None;
+    let mut bind: Option<std::net::IpAddr> = None;
+    let mut port: Option<u16> = None;
+    let mut console = true;
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--config" | "-c" => config_path = Some(value()?.into()),
+            "--bind" | "-b" => bind = Some(value()?.parse()?),
+            "--port" | "-p" => port = Some(value()?.parse()?),
+            "--no-console" => console = false,
+            other => return Err(format!("unknown argument {other}").into()),
+        }
+    }
+
+    let mut cfg = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    if let Some(bind) = bind {
+        cfg.bind = bind;
+    }
+    if let Some(port) = port {
+        cfg.port = port;
+    }
+    cfg.apply_env(|name| std::env::var(name).ok())?;
+
+    init_tracing(&cfg.log);
+    eprintln!("effective config:\n{}", cfg.to_toml());
+    if let Some(dir) = &cfg.replay_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let handle = snake_server::ServerBuilder::from_config(&cfg)
+        .shutdown(async {
+            shutdown_signal().await;
+            eprintln!("draining; signal again to stop now");
+            tokio::spawn(async {
+                shutdown_signal().await;
+                console::restore_terminal();
+                std::process::exit(130);
+            });
+        })
+        .start()
+        .await?;
+    if console {
+        console::spawn(handle.admin());
+    }
+    let stopped = handle.wait().await;
+    console::restore_terminal();
+    stopped
+}
+
+/// Ctrl-C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+fn tournament(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use snake_server::tournament::{self, Format, TournamentConfig};
+
+    let mut cfg = TournamentConfig::default();
+    let mut swiss = false;
+    let mut rounds: Option<u32> = None;
+    let mut bots: Option<String> = None;
+    let mut out_dir: Option<std::path::PathBuf> = None;
 
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "--format" => match value()?.as_str() {
+                "round-robin" | "rr" => swiss = false,
+                "swiss" => swiss = true,
+                other => return Err(format!("unknown format {other} (round-robin, swiss)").into()),
+            },
+            "--rounds" => rounds = Some(value()?.parse()?),
+            "--games" => cfg.games = value()?.parse()?,
+            "--mode" => {
+                cfg.rules.mode = serde_json::from_value(serde_json::Value::String(value()?))?
+            }
+            "--seed" => cfg.seed = value()?.parse()?,
+            "--max-ticks" => cfg.max_ticks = value()?.parse()?,
+            "--threads" => cfg.threads = value()?.parse()?,
+            "--bots" => bots = Some(value()?),
+            "--out" => out_dir = Some(value()?.into()),
+            other => return Err(format!("unknown argument {other}").into()),
+        }
+    }
+
+    let registry = tournament::registry();
+    let entrants = match bots {
+        None => registry,
+        Some(list) => list
+            .split(',')
+            .map(|name| {
+                registry
+                    .iter()
+                    .find(|e| e.name == name.trim())
+                    .cloned()
+                    .ok_or(format!("unknown bot {name}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    if entrants.len() < 2 {
+        return Err("a tournament needs at least two bots".into());
+    }
+    if swiss {
+        // enough rounds to separate the field by default
+        let default = (usize::BITS - (entrants.len() - 1).leading_zeros()).max(1);
+        cfg.format = Format::Swiss {
+            rounds: rounds.unwrap_or(default),
+        };
+    }
+
+    let table = tournament::run(&entrants, &cfg);
+    let markdown = table.to_markdown();
+    print!("{markdown}");
+    if let Some(dir) = out_dir {
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("standings.json"), table.to_json())?;
+        std::fs::write(dir.join("standings.md"), markdown)?;
+        eprintln!("wrote {}", dir.display());
+    }
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    init_tracing();
-    snake_server::run().await
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("tournament") => tournament(&args[1..]),
+        Some("serve") => serve(&args[1..]).await,
+        // no subcommand: serve with flags only
+        _ => serve(&args).await,
+    }
 }
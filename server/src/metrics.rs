@@ -0,0 +1,317 @@
+//! Server counters and the `/metrics` endpoint in Prometheus text format.
+//!
+//! Process-wide counters are plain atomics on `Metrics`. Per-room figures
+//! (connections, tick durations) live on each `Room` and are read when the
+//! endpoint is scraped, so a closed room's series simply disappear.
+
+use crate::error::ProtocolError;
+use crate::model::{ErrorCode, C2S};
+use crate::room::Room;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds (seconds) for tick duration buckets.
+const TICK_BUCKETS: [f64; 10] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1,
+];
+
+/// A fixed-bucket latency histogram; owned by whoever records into it.
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// Non-cumulative counts per bucket in `TICK_BUCKETS`, plus one for `+Inf`.
+    buckets: [u64; TICK_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, d: Duration) {
+        let secs = d.as_secs_f64();
+        let i = TICK_BUCKETS
+            .iter()
+            .position(|le| secs <= *le)
+            .unwrap_or(TICK_BUCKETS.len());
+        self.buckets[i] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (le, n) in TICK_BUCKETS.iter().zip(&self.buckets) {
+            cumulative += n;
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{le}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+/// Process-wide counters, shared by every connection and the ticker.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub connections_opened: AtomicU64,
+    pub connections_closed: AtomicU64,
+    /// Turned away by a room or player limit.
+    pub connections_rejected: AtomicU64,
+    /// Bytes of serialized `State` messages written to sockets.
+    pub snapshot_bytes_sent: AtomicU64,
+    /// Inbound messages, indexed like `C2S::KINDS`.
+    messages_received: [AtomicU64; C2S::KINDS.len()],
+    /// Text frames that weren't a valid `C2S`.
+    pub parse_failures: AtomicU64,
+    /// `S2C::Error` replies, indexed like `ErrorCode::ALL`.
+    protocol_errors: [AtomicU64; ErrorCode::ALL.len()],
+    /// Outbound messages that failed to serialize and were skipped.
+    pub serialize_failures: AtomicU64,
+    /// Ticker passes that took longer than one tick period.
+    pub tick_overruns: AtomicU64,
+    /// Messages dropped for exceeding their type's rate, indexed like `C2S::KINDS`.
+    rate_limited: [AtomicU64; C2S::KINDS.len()],
+    pub oversized_frames: AtomicU64,
+    /// Inputs dropped because the player's queue was full.
+    pub input_overflows: AtomicU64,
+    /// Connections cut off for repeated violations.
+    pub flood_disconnects: AtomicU64,
+    /// Connections dropped for falling too far behind on outbound messages.
+    pub slow_client_disconnects: AtomicU64,
+}
+
+impl Metrics {
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn received(&self, msg: &C2S) {
+        Self::inc(&self.messages_received[msg.kind_index()]);
+    }
+
+    pub fn protocol_error(&self, e: &ProtocolError) {
+        let code = e.code();
+        let i = ErrorCode::ALL.iter().position(|c| *c == code).unwrap_or(0);
+        Self::inc(&self.protocol_errors[i]);
+        match e {
+            ProtocolError::Malformed(_) | ProtocolError::TooManyMalformed => {
+                Self::inc(&self.parse_failures)
+            }
+            ProtocolError::TooLarge { .. } => Self::inc(&self.oversized_frames),
+            ProtocolError::QueueFull => Self::inc(&self.input_overflows),
+            ProtocolError::RateLimited(kind) => {
+                if let Some(i) = C2S::KINDS.iter().position(|k| k == kind) {
+                    Self::inc(&self.rate_limited[i]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Render everything in Prometheus text exposition format.
+    pub fn render<'a>(&self, rooms: impl Iterator<Item = (&'a str, &'a Room)>) -> String {
+        let mut out = String::new();
+        let get = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        let counter = |out: &mut String, name: &str, help: &str, v: u64| {
+            let _ = writeln!(
+                out,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {v}"
+            );
+        };
+
+        let rooms: Vec<_> = rooms.collect();
+        let _ = writeln!(
+            out,
+            "# HELP snake_rooms_active Rooms currently open.\n# TYPE snake_rooms_active gauge\nsnake_rooms_active {}",
+            rooms.len()
+        );
+        out.push_str("# HELP snake_room_connections Connected humans per room.\n");
+        out.push_str("# TYPE snake_room_connections gauge\n");
+        for (name, room) in &rooms {
+            let _ = writeln!(
+                out,
+                "snake_room_connections{{room=\"{}\"}} {}",
+                escape(name),
+                room.humans()
+            );
+        }
+        out.push_str(
+            "# HELP snake_outbound_queue_depth Messages waiting to be written, per connection.\n",
+        );
+        out.push_str("# TYPE snake_outbound_queue_depth gauge\n");
+        for (name, room) in &rooms {
+            for p in room.players.values() {
+                if let Some(tx) = &p.tx {
+                    let _ = writeln!(
+                        out,
+                        "snake_outbound_queue_depth{{room=\"{}\",player=\"{}\"}} {}",
+                        escape(name),
+                        escape(&p.id),
+                        tx.depth()
+                    );
+                }
+            }
+        }
+        out.push_str("# HELP snake_player_rtt_seconds Smoothed round-trip time, per connection.\n");
+        out.push_str("# TYPE snake_player_rtt_seconds gauge\n");
+        for (name, room) in &rooms {
+            for p in room.players.values() {
+                if let Some(ms) = p.rtt.rtt_ms() {
+                    let _ = writeln!(
+                        out,
+                        "snake_player_rtt_seconds{{room=\"{}\",player=\"{}\"}} {}",
+                        escape(name),
+                        escape(&p.id),
+                        ms as f64 / 1000.0
+                    );
+                }
+            }
+        }
+        out.push_str("# HELP snake_room_tick_duration_seconds Time spent in Room::step.\n");
+        out.push_str("# TYPE snake_room_tick_duration_seconds histogram\n");
+        for (name, room) in &rooms {
+            let labels = format!("room=\"{}\"", escape(name));
+            room.tick_time
+                .write(&mut out, "snake_room_tick_duration_seconds", &labels);
+        }
+
+        counter(
+            &mut out,
+            "snake_connections_opened_total",
+            "WebSocket connections accepted into a room.",
+            get(&self.connections_opened),
+        );
+        counter(
+            &mut out,
+            "snake_connections_closed_total",
+            "WebSocket connections that left their room.",
+            get(&self.connections_closed),
+        );
+        counter(
+            &mut out,
+            "snake_connections_rejected_total",
+            "Connections turned away by a room or player limit.",
+            get(&self.connections_rejected),
+        );
+        counter(
+            &mut out,
+            "snake_snapshot_bytes_sent_total",
+            "Bytes of state snapshots written to clients.",
+            get(&self.snapshot_bytes_sent),
+        );
+        out.push_str("# HELP snake_messages_received_total Client messages by type.\n");
+        out.push_str("# TYPE snake_messages_received_total counter\n");
+        for (kind, n) in C2S::KINDS.iter().zip(&self.messages_received) {
+            let _ = writeln!(
+                out,
+                "snake_messages_received_total{{type=\"{kind}\"}} {}",
+                get(n)
+            );
+        }
+        counter(
+            &mut out,
+            "snake_parse_failures_total",
+            "Client frames that could not be parsed.",
+            get(&self.parse_failures),
+        );
+        out.push_str(
+            "# HELP snake_protocol_errors_total Error replies sent to clients, by code.\n",
+        );
+        out.push_str("# TYPE snake_protocol_errors_total counter\n");
+        for (code, n) in ErrorCode::ALL.iter().zip(&self.protocol_errors) {
+            let _ = writeln!(
+                out,
+                "snake_protocol_errors_total{{code=\"{}\"}} {}",
+                code.as_str(),
+                get(n)
+            );
+        }
+        counter(
+            &mut out,
+            "snake_serialize_failures_total",
+            "Outbound messages that could not be serialized.",
+            get(&self.serialize_failures),
+        );
+        counter(
+            &mut out,
+            "snake_tick_overruns_total",
+            "Ticker passes that took longer than one tick period.",
+            get(&self.tick_overruns),
+        );
+        out.push_str(
+            "# HELP snake_rate_limited_total Client messages dropped by rate limits, by type.\n",
+        );
+        out.push_str("# TYPE snake_rate_limited_total counter\n");
+        for (kind, n) in C2S::KINDS.iter().zip(&self.rate_limited) {
+            let _ = writeln!(
+                out,
+                "snake_rate_limited_total{{type=\"{kind}\"}} {}",
+                get(n)
+            );
+        }
+        counter(
+            &mut out,
+            "snake_oversized_frames_total",
+            "Client frames dropped for exceeding max_frame_bytes.",
+            get(&self.oversized_frames),
+        );
+        counter(
+            &mut out,
+            "snake_input_overflows_total",
+            "Inputs dropped because the player's queue was full.",
+            get(&self.input_overflows),
+        );
+        counter(
+            &mut out,
+            "snake_flood_disconnects_total",
+            "Connections dropped for repeated violations.",
+            get(&self.flood_disconnects),
+        );
+        counter(
+            &mut out,
+            "snake_slow_client_disconnects_total",
+            "Connections dropped for not keeping up with outbound messages.",
+            get(&self.slow_client_disconnects),
+        );
+        out
+    }
+}
+
+/// Escape a label value per the text format.
+fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::RoomRules;
+
+    #[test]
+    fn renders_rooms_counters_and_histograms() {
+        let metrics = Metrics::default();
+        Metrics::inc(&metrics.connections_opened);
+        metrics.received(&C2S::Ping { t: 1 });
+        metrics.received(&C2S::Start);
+        metrics.received(&C2S::Ping { t: 2 });
+        metrics.protocol_error(&ProtocolError::RateLimited("start"));
+
+        let mut room = Room::new("a\"b", 22, 22, 10, RoomRules::default());
+        room.tick_time.observe(Duration::from_micros(300));
+        room.tick_time.observe(Duration::from_secs(1));
+
+        let text = metrics.render([("a\"b", &room)].into_iter());
+        assert!(text.contains("snake_rooms_active 1\n"));
+        assert!(text.contains("snake_room_connections{room=\"a\\\"b\"} 0\n"));
+        assert!(text.contains(
+            "snake_room_tick_duration_seconds_bucket{room=\"a\\\"b\",le=\"0.0005\"} 1\n"
+        ));
+        assert!(text
+            .contains("snake_room_tick_duration_seconds_bucket{room=\"a\\\"b\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("snake_messages_received_total{type=\"ping\"} 2\n"));
+        assert!(text.contains("snake_connections_opened_total 1\n"));
+        assert!(text.contains("snake_rate_limited_total{type=\"start\"} 1\n"));
+        assert!(text.contains("snake_protocol_errors_total{code=\"rate_limited\"} 1\n"));
+    }
+}
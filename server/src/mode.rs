@@ -0,0 +1,152 @@
+//! Room rules. Everything that differs between game modes lives behind the
+//! [`GameMode`] trait; `Room::step` only does movement and collisions and asks
+//! the mode what those events mean.
+
+mod classic;
+mod royale;
+mod team;
+mod time_attack;
+
+pub use classic::Classic;
+pub use royale::BattleRoyale;
+pub use team::Teams;
+pub use time_attack::TimeAttack;
+
+use crate::model::{ModeKind, TeamScore};
+use crate::room::{Room, RoomRules};
+use std::time::Duration;
+
+/// A player's place in the current standings. Tied players share a rank.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub id: String,
+    pub rank: u32,
+    pub score: u32,
+}
+
+pub trait GameMode: Send + Sync {
+    fn kind(&self) -> ModeKind;
+
+    /// A player was added to the room (snake not placed yet).
+    fn on_join(&mut self, room: &mut Room, id: &str) {
+        room.place_player(id);
+    }
+
+    /// The room is starting a fresh round; every player is dead and unplaced.
+    fn on_round_start(&mut self, room: &mut Room) {
+        let ids: Vec<String> = room.players.keys().cloned().collect();
+        for id in ids {
+            room.place_player(&id);
+        }
+    }
+
+    /// `id` moved onto the food this tick.
+    fn on_eat(&mut self, room: &mut Room, id: &str) {
+        if let Some(p) = room.players.get_mut(id) {
+            p.score += 1;
+        }
+    }
+
+    /// `id` died this tick; `alive` is already cleared.
+    fn on_death(&mut self, room: &mut Room, id: &str);
+
+    /// Runs once per tick after moves, food and deaths are resolved.
+    fn on_tick(&mut self, _room: &mut Room) {}
+
+    /// Whether a manual respawn request from `id` is honoured.
+    fn can_respawn(&self, room: &Room, id: &str) -> bool;
+
+    /// The player asked to play again after running out of lives.
+    fn on_restart(&mut self, room: &mut Room, id: &str);
+
+    /// Whether rounds in this mode end on their own; a drain only waits for those.
+    fn has_round_end(&self) -> bool {
+        false
+    }
+
+    fn is_round_over(&self, _room: &Room) -> bool {
+        false
+    }
+
+    /// Ticks left on the round clock, if this mode plays against one.
+    fn ticks_left(&self, _room: &Room) -> Option<u64> {
+        None
+    }
+
+    /// Whether `mover`'s head running into `owner`'s body kills it.
+    fn body_hit_is_lethal(&self, _room: &Room, _mover: &str, _owner: &str) -> bool {
+        true
+    }
+
+    /// Shared team scores, for modes that have teams.
+    fn team_scores(&self) -> Vec<TeamScore> {
+        vec![]
+    }
+
+    /// Current standings, best first.
+    fn scoring(&self, room: &Room) -> Vec<Standing> {
+        rank_by(room.players.values().map(|p| (p.id.clone(), p.score)))
+    }
+}
+
+/// Build the rules for a new room ticking every `tick`.
+pub fn create(rules: &RoomRules, tick: Duration) -> Box<dyn GameMode> {
+    match rules.mode {
+        ModeKind::Classic => Box::new(Classic),
+        ModeKind::BattleRoyale => Box::new(BattleRoyale::default()),
+        ModeKind::TimeAttack => Box::new(TimeAttack::new(time_attack::ROUND_LENGTH, tick)),
+        ModeKind::Team => Box::new(Teams::new(rules.teams, rules.friendly_fire)),
+    }
+}
+
+/// Rank `(id, key)` pairs by key, highest first; equal keys share a rank.
+pub fn rank_by(entries: impl IntoIterator<Item = (String, u32)>) -> Vec<Standing> {
+    let mut entries: Vec<(String, u32)> = entries.into_iter().collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let mut out: Vec<Standing> = Vec::with_capacity(entries.len());
+    for (i, (id, score)) in entries.into_iter().enumerate() {
+        let rank = match out.last() {
+            Some(prev) if prev.score == score => prev.rank,
+            _ => i as u32 + 1,
+        };
+        out.push(Standing { id, rank, score });
+    }
+    out
+}
+
+/// The winner is the only player in first place; a shared first place is a draw.
+pub fn sole_winner(standings: &[Standing]) -> Option<String> {
+    match standings {
+        [first, second, ..] if second.rank == first.rank => None,
+        [first, ..] => Some(first.id.clone()),
+        [] => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::model::ModeKind;
+    use crate::outbox;
+    use crate::room::{Room, RoomRules};
+
+    /// A room with no sockets behind it; outbound messages go to dropped receivers.
+    pub fn room(kind: ModeKind, players: &[&str]) -> Room {
+        with_rules(
+            RoomRules {
+                mode: kind,
+                ..Default::default()
+            },
+            players,
+        )
+    }
+
+    pub fn with_rules(rules: RoomRules, players: &[&str]) -> Room {
+        let mut room = Room::new("test", 22, 22, 10, rules);
+        for id in players {
+            let (tx, _rx) = outbox::channel(64, 64);
+            room.add_player(id.to_string(), id.to_string(), tx);
+        }
+        room.start();
+        room
+    }
+}
@@ -0,0 +1,105 @@
+use super::GameMode;
+use crate::model::ModeKind;
+use crate::room::Room;
+
+/// Endless play: three lives each, one point per apple, and everyone with
+/// lives left respawns together once all of them are dead.
+#[derive(Debug, Default)]
+pub struct Classic;
+
+impl GameMode for Classic {
+    fn kind(&self) -> ModeKind {
+        ModeKind::Classic
+    }
+
+    fn on_death(&mut self, room: &mut Room, id: &str) {
+        if let Some(p) = room.players.get_mut(id) {
+            p.lives = p.lives.saturating_sub(1);
+        }
+    }
+
+    fn on_tick(&mut self, room: &mut Room) {
+        // auto-respawn if all remaining-with-lives are dead (solo-friendly)
+        let mut with_lives = room.players.values().filter(|p| p.lives > 0).peekable();
+        if with_lives.peek().is_some() && with_lives.all(|p| !p.alive) {
+            let ids: Vec<String> = room
+                .players
+                .values()
+                .filter(|p| p.lives > 0)
+                .map(|p| p.id.clone())
+                .collect();
+            for id in ids {
+                room.place_player(&id);
+            }
+        }
+    }
+
+    fn can_respawn(&self, room: &Room, id: &str) -> bool {
+        room.players.get(id).is_some_and(|p| p.lives > 0)
+    }
+
+    fn on_restart(&mut self, room: &mut Room, id: &str) {
+        if let Some(p) = room.players.get_mut(id) {
+            p.lives = 3;
+        }
+        room.place_player(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mode::testing::room;
+    use crate::model::{Cell, Dir, ModeKind};
+
+    #[test]
+    fn eating_scores_a_point_and_grows() {
+        let mut room = room(ModeKind::Classic, &["a"]);
+        let head = room.players["a"].snake[0];
+        room.food = Cell(head.0 + 1, head.1);
+        room.step();
+        let a = &room.players["a"];
+        assert_eq!(a.score, 1);
+        assert_eq!(a.snake.len(), 4);
+        assert_ne!(room.food, Cell(head.0 + 1, head.1));
+    }
+
+    #[test]
+    fn wall_costs_a_life_and_solo_player_respawns() {
+        let mut room = room(ModeKind::Classic, &["a"]);
+        room.queue_input("a", Dir::Up);
+        for _ in 0..room.grid_h {
+            room.step();
+            if room.players["a"].lives < 3 {
+                break;
+            }
+        }
+        let a = &room.players["a"];
+        assert_eq!(a.lives, 2);
+        assert!(a.alive, "solo player should be auto-respawned");
+        assert!(!room.round_over);
+    }
+
+    #[test]
+    fn no_respawn_without_lives_until_restart() {
+        let mut room = room(ModeKind::Classic, &["a", "b"]);
+        room.players.get_mut("a").unwrap().lives = 0;
+        room.players.get_mut("a").unwrap().alive = false;
+        room.respawn_player("a");
+        assert!(!room.players["a"].alive);
+
+        room.restart_player("a");
+        assert!(room.players["a"].alive);
+        assert_eq!(room.players["a"].lives, 3);
+    }
+
+    #[test]
+    fn head_to_head_kills_both() {
+        let mut room = room(ModeKind::Classic, &["a", "b"]);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(5, 5), Cell(4, 5), Cell(3, 5)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(7, 5), Cell(8, 5), Cell(9, 5)];
+        room.players.get_mut("b").unwrap().dir = Dir::Left;
+        room.step();
+        assert_eq!(room.players["a"].lives, 2);
+        assert_eq!(room.players["b"].lives, 2);
+    }
+}
@@ -0,0 +1,217 @@
+use super::{GameMode, Standing};
+use crate::model::ModeKind;
+use crate::room::Room;
+
+// The storm closes in by one ring every this many ticks...
+const STORM_SHRINK_TICKS: u64 = 50;
+// ...until the safe zone is this many cells across.
+const STORM_MIN_SIZE: i32 = 6;
+
+/// Last snake standing: no respawns, the arena shrinks over time and the
+/// round ends when one snake (or none) is left.
+#[derive(Debug, Default)]
+pub struct BattleRoyale {
+    /// Who entered the current round; late joiners sit it out.
+    entrants: Vec<String>,
+    /// `(id, tick)` in elimination order, for the final standings.
+    eliminated: Vec<(String, u64)>,
+}
+
+impl GameMode for BattleRoyale {
+    fn kind(&self) -> ModeKind {
+        ModeKind::BattleRoyale
+    }
+
+    fn on_join(&mut self, room: &mut Room, id: &str) {
+        // late joiners sit out a round that is already under way
+        if room.started && !room.round_over && room.round_ticks > 0 {
+            if let Some(p) = room.players.get_mut(id) {
+                p.lives = 0;
+            }
+            return;
+        }
+        self.entrants.push(id.to_string());
+        room.place_player(id);
+    }
+
+    fn on_round_start(&mut self, room: &mut Room) {
+        self.entrants = room.players.keys().cloned().collect();
+        self.eliminated.clear();
+        let ids: Vec<String> = room.players.keys().cloned().collect();
+        for id in ids {
+            room.place_player(&id);
+        }
+    }
+
+    fn on_death(&mut self, room: &mut Room, id: &str) {
+        if let Some(p) = room.players.get_mut(id) {
+            p.lives = 0;
+        }
+        self.eliminated.push((id.to_string(), room.round_ticks));
+    }
+
+    fn on_tick(&mut self, room: &mut Room) {
+        if room.round_ticks.is_multiple_of(STORM_SHRINK_TICKS) && room.arena.shrink(STORM_MIN_SIZE)
+        {
+            tracing::debug!("storm: arena now {:?}", room.arena);
+            if !room.arena.contains(room.food) {
+                room.food = room.random_empty();
+            }
+        }
+    }
+
+    fn can_respawn(&self, _room: &Room, _id: &str) -> bool {
+        // no second chances
+        false
+    }
+
+    fn on_restart(&mut self, room: &mut Room, _id: &str) {
+        // only between rounds, where it kicks off a fresh round for everyone
+        if room.round_over {
+            room.reset_round();
+        }
+    }
+
+    fn has_round_end(&self) -> bool {
+        true
+    }
+
+    fn is_round_over(&self, room: &Room) -> bool {
+        let alive = room.players.values().filter(|p| p.alive).count();
+        if self.entrants.len() > 1 {
+            alive <= 1
+        } else {
+            alive == 0
+        }
+    }
+
+    /// Survivors first, then by how long each snake lasted; players who sat
+    /// the round out come last.
+    fn scoring(&self, room: &Room) -> Vec<Standing> {
+        let lasted = |id: &str| {
+            if !self.entrants.iter().any(|e| e == id) {
+                return None;
+            }
+            let out = self.eliminated.iter().find(|(e, _)| e == id);
+            Some(out.map_or(u64::MAX, |(_, tick)| *tick))
+        };
+        let mut entries: Vec<(&str, Option<u64>, u32)> = room
+            .players
+            .values()
+            .map(|p| (p.id.as_str(), lasted(&p.id), p.score))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let mut out: Vec<Standing> = Vec::with_capacity(entries.len());
+        for (i, (id, tick, score)) in entries.iter().enumerate() {
+            let rank = match out.last() {
+                Some(prev) if entries[i - 1].1 == *tick => prev.rank,
+                _ => i as u32 + 1,
+            };
+            out.push(Standing {
+                id: id.to_string(),
+                rank,
+                score: *score,
+            });
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mode::testing::room;
+    use crate::model::{Arena, Cell, Dir, ModeKind};
+
+    #[test]
+    fn storm_shrinks_the_arena() {
+        let mut room = room(ModeKind::BattleRoyale, &["a", "b"]);
+        room.food = Cell(5, 20);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(10, 8), Cell(9, 8), Cell(8, 8)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(10, 14), Cell(9, 14), Cell(8, 14)];
+        let turns = [Dir::Down, Dir::Left, Dir::Up, Dir::Right];
+        for tick in 0..super::STORM_SHRINK_TICKS {
+            // circle on the spot so nobody hits the wall
+            let d = turns[(tick % 4) as usize];
+            room.queue_input("a", d);
+            room.queue_input("b", d);
+            room.step();
+        }
+        assert_eq!(
+            room.arena,
+            Arena {
+                min: Cell(1, 1),
+                max: Cell(20, 20)
+            }
+        );
+    }
+
+    #[test]
+    fn entering_the_storm_is_lethal_and_there_is_no_respawn() {
+        let mut room = room(ModeKind::BattleRoyale, &["a", "b"]);
+        room.arena.shrink(2);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(20, 5), Cell(19, 5), Cell(18, 5)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(10, 15), Cell(9, 15), Cell(8, 15)];
+        room.step();
+        assert!(!room.players["a"].alive);
+        assert_eq!(room.players["a"].lives, 0);
+        room.respawn_player("a");
+        assert!(!room.players["a"].alive);
+    }
+
+    #[test]
+    fn last_snake_standing_wins() {
+        let mut room = room(ModeKind::BattleRoyale, &["a", "b", "c"]);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(21, 2), Cell(20, 2), Cell(19, 2)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(10, 10), Cell(9, 10), Cell(8, 10)];
+        room.players.get_mut("c").unwrap().snake = vec![Cell(21, 18), Cell(20, 18), Cell(19, 18)];
+        room.step();
+        assert!(room.round_over);
+        assert_eq!(room.winner.as_deref(), Some("b"));
+
+        let standings = room.standings();
+        assert_eq!(standings[0].id, "b");
+        assert_eq!(standings[0].rank, 1);
+        assert_eq!(standings[1].rank, 2);
+        assert_eq!(standings[2].rank, 2);
+
+        // board is frozen until a restart opens a new round
+        let before = room.players["b"].snake.clone();
+        room.step();
+        assert_eq!(room.players["b"].snake, before);
+        room.restart_player("a");
+        assert!(!room.round_over);
+        assert!(room.players.values().all(|p| p.alive));
+    }
+
+    #[test]
+    fn late_joiner_does_not_share_the_win() {
+        let mut room = room(ModeKind::BattleRoyale, &["a", "b"]);
+        room.step();
+        let (tx, _rx) = crate::outbox::channel(64, 64);
+        room.add_player("c".into(), "c".into(), tx);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(21, 2), Cell(20, 2), Cell(19, 2)];
+        room.players.get_mut("a").unwrap().dir = Dir::Right;
+        room.players.get_mut("b").unwrap().snake = vec![Cell(10, 10), Cell(9, 10), Cell(8, 10)];
+        room.players.get_mut("b").unwrap().dir = Dir::Right;
+        room.step();
+        assert!(room.round_over);
+        assert_eq!(room.winner.as_deref(), Some("b"));
+
+        let ranks: Vec<(String, u32)> = room
+            .standings()
+            .into_iter()
+            .map(|s| (s.id, s.rank))
+            .collect();
+        assert_eq!(ranks, [("b".into(), 1), ("a".into(), 2), ("c".into(), 3)]);
+    }
+
+    #[test]
+    fn late_joiner_spectates() {
+        let mut room = room(ModeKind::BattleRoyale, &["a", "b"]);
+        room.step();
+        let (tx, _rx) = crate::outbox::channel(64, 64);
+        room.add_player("c".into(), "c".into(), tx);
+        assert!(!room.players["c"].alive);
+        assert_eq!(room.players["c"].lives, 0);
+    }
+}
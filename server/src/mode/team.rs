@@ -0,0 +1,174 @@
+use super::{Classic, GameMode};
+use crate::model::{ModeKind, TeamScore};
+use crate::room::Room;
+
+/// Classic rules played in 2–4 teams. Apples count towards a shared team
+/// score, and with friendly fire off teammates can slither through each other.
+#[derive(Debug)]
+pub struct Teams {
+    friendly_fire: bool,
+    scores: Vec<u32>,
+    rules: Classic,
+}
+
+impl Teams {
+    pub fn new(teams: u8, friendly_fire: bool) -> Self {
+        Self {
+            friendly_fire,
+            scores: vec![0; teams.clamp(2, 4) as usize],
+            rules: Classic,
+        }
+    }
+
+    fn team_of(room: &Room, id: &str) -> Option<u8> {
+        room.players.get(id).and_then(|p| p.team)
+    }
+}
+
+impl GameMode for Teams {
+    fn kind(&self) -> ModeKind {
+        ModeKind::Team
+    }
+
+    fn on_join(&mut self, room: &mut Room, id: &str) {
+        // fill the smallest team, lowest index first
+        let mut sizes = vec![0usize; self.scores.len()];
+        for p in room.players.values() {
+            if let Some(t) = p.team {
+                sizes[t as usize] += 1;
+            }
+        }
+        let team = (0..sizes.len()).min_by_key(|&t| sizes[t]).unwrap_or(0) as u8;
+        if let Some(p) = room.players.get_mut(id) {
+            p.team = Some(team);
+        }
+        room.place_player(id);
+    }
+
+    fn on_round_start(&mut self, room: &mut Room) {
+        self.scores.iter_mut().for_each(|s| *s = 0);
+        self.rules.on_round_start(room);
+    }
+
+    fn on_eat(&mut self, room: &mut Room, id: &str) {
+        self.rules.on_eat(room, id);
+        if let Some(t) = Self::team_of(room, id) {
+            self.scores[t as usize] += 1;
+        }
+    }
+
+    fn on_death(&mut self, room: &mut Room, id: &str) {
+        self.rules.on_death(room, id);
+    }
+
+    fn on_tick(&mut self, room: &mut Room) {
+        self.rules.on_tick(room);
+    }
+
+    fn can_respawn(&self, room: &Room, id: &str) -> bool {
+        self.rules.can_respawn(room, id)
+    }
+
+    fn on_restart(&mut self, room: &mut Room, id: &str) {
+        self.rules.on_restart(room, id);
+    }
+
+    fn body_hit_is_lethal(&self, room: &Room, mover: &str, owner: &str) -> bool {
+        if self.friendly_fire || mover == owner {
+            return true;
+        }
+        Self::team_of(room, mover) != Self::team_of(room, owner)
+    }
+
+    fn team_scores(&self) -> Vec<TeamScore> {
+        self.scores
+            .iter()
+            .enumerate()
+            .map(|(t, &score)| TeamScore {
+                team: t as u8,
+                score,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mode::testing::with_rules;
+    use crate::model::{Cell, Dir, ModeKind};
+    use crate::room::{Room, RoomRules};
+
+    fn team_room(teams: u8, friendly_fire: bool, players: &[&str]) -> Room {
+        let rules = RoomRules {
+            mode: ModeKind::Team,
+            teams,
+            friendly_fire,
+            ..Default::default()
+        };
+        with_rules(rules, players)
+    }
+
+    /// `a` heads down into `c`'s body; `a` and `c` are teammates.
+    fn collide_with_teammate(room: &mut Room) {
+        room.food = Cell(0, 21);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(5, 4), Cell(4, 4), Cell(3, 4)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(5, 15), Cell(4, 15), Cell(3, 15)];
+        room.players.get_mut("c").unwrap().snake = vec![Cell(7, 5), Cell(6, 5), Cell(5, 5)];
+        room.players.get_mut("a").unwrap().dir = Dir::Down;
+        room.step();
+    }
+
+    #[test]
+    fn players_are_spread_across_teams() {
+        let room = team_room(3, false, &["a", "b", "c", "d"]);
+        let mut per_team = [0; 3];
+        for p in room.players.values() {
+            per_team[p.team.unwrap() as usize] += 1;
+        }
+        per_team.sort();
+        assert_eq!(per_team, [1, 1, 2]);
+    }
+
+    #[test]
+    fn apples_count_for_the_team() {
+        let mut room = team_room(2, false, &["a", "b", "c"]);
+        let team = room.players["a"].team.unwrap();
+        let head = room.players["a"].snake[0];
+        room.food = Cell(head.0 + 1, head.1);
+        room.step();
+        let scores = room.snapshot();
+        let crate::model::S2C::State { teams, .. } = scores else {
+            unreachable!()
+        };
+        assert_eq!(teams[team as usize].score, 1);
+        assert_eq!(teams[1 - team as usize].score, 0);
+    }
+
+    #[test]
+    fn friendly_fire_off_lets_teammates_pass() {
+        let mut room = team_room(2, false, &["a", "b", "c"]);
+        assert_eq!(room.players["a"].team, room.players["c"].team);
+        collide_with_teammate(&mut room);
+        assert_eq!(room.players["a"].lives, 3);
+        assert!(room.players["a"].alive);
+    }
+
+    #[test]
+    fn friendly_fire_on_kills() {
+        let mut room = team_room(2, true, &["a", "b", "c"]);
+        collide_with_teammate(&mut room);
+        assert_eq!(room.players["a"].lives, 2);
+    }
+
+    #[test]
+    fn enemy_body_always_kills() {
+        let mut room = team_room(2, false, &["a", "b", "c"]);
+        room.food = Cell(0, 21);
+        room.players.get_mut("a").unwrap().snake = vec![Cell(5, 4), Cell(4, 4), Cell(3, 4)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(7, 5), Cell(6, 5), Cell(5, 5)];
+        room.players.get_mut("c").unwrap().snake = vec![Cell(5, 15), Cell(4, 15), Cell(3, 15)];
+        room.players.get_mut("a").unwrap().dir = Dir::Down;
+        room.step();
+        assert_eq!(room.players["a"].lives, 2);
+    }
+}
@@ -0,0 +1,127 @@
+use super::GameMode;
+use crate::model::ModeKind;
+use crate::room::Room;
+use std::time::Duration;
+
+/// Default round length.
+pub const ROUND_LENGTH: Duration = Duration::from_secs(120);
+// Points lost per death (never below zero).
+const DEATH_PENALTY: u32 = 3;
+
+/// Race the clock: unlimited respawns, each death costs points, and the
+/// highest score when time runs out wins.
+#[derive(Debug)]
+pub struct TimeAttack {
+    round_ticks: u64,
+}
+
+impl TimeAttack {
+    pub fn new(round: Duration, tick: Duration) -> Self {
+        let tick_ms = tick.as_millis().max(1);
+        Self {
+            round_ticks: (round.as_millis() / tick_ms).max(1) as u64,
+        }
+    }
+}
+
+impl GameMode for TimeAttack {
+    fn kind(&self) -> ModeKind {
+        ModeKind::TimeAttack
+    }
+
+    fn on_death(&mut self, room: &mut Room, id: &str) {
+        if let Some(p) = room.players.get_mut(id) {
+            p.score = p.score.saturating_sub(DEATH_PENALTY);
+        }
+        room.place_player(id);
+    }
+
+    fn can_respawn(&self, _room: &Room, _id: &str) -> bool {
+        // deaths respawn straight away
+        false
+    }
+
+    fn on_restart(&mut self, room: &mut Room, _id: &str) {
+        if room.round_over {
+            room.reset_round();
+        }
+    }
+
+    fn has_round_end(&self) -> bool {
+        true
+    }
+
+    fn is_round_over(&self, room: &Room) -> bool {
+        room.round_ticks >= self.round_ticks
+    }
+
+    fn ticks_left(&self, room: &Room) -> Option<u64> {
+        Some(self.round_ticks.saturating_sub(room.round_ticks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mode::testing::room;
+    use crate::model::{Cell, Dir, ModeKind, S2C};
+
+    #[test]
+    fn death_costs_score_not_lives() {
+        let mut room = room(ModeKind::TimeAttack, &["a"]);
+        room.players.get_mut("a").unwrap().score = 5;
+        room.players.get_mut("a").unwrap().snake = vec![Cell(21, 3), Cell(20, 3), Cell(19, 3)];
+        room.step();
+        let a = &room.players["a"];
+        assert_eq!(a.score, 2);
+        assert_eq!(a.lives, 3);
+        assert!(a.alive, "time attack respawns immediately");
+    }
+
+    #[test]
+    fn clock_runs_down_and_ends_the_round() {
+        let mut room = room(ModeKind::TimeAttack, &["a", "b"]);
+        let total = match room.snapshot() {
+            S2C::State { time_left_ms, .. } => time_left_ms.unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!(total, 120_000);
+
+        room.players.get_mut("b").unwrap().score = 4;
+        // keep both snakes circling in their own corner until the buzzer
+        room.players.get_mut("a").unwrap().snake = vec![Cell(4, 4), Cell(3, 4), Cell(2, 4)];
+        room.players.get_mut("b").unwrap().snake = vec![Cell(4, 16), Cell(3, 16), Cell(2, 16)];
+        room.food = Cell(15, 10);
+        let turns = [Dir::Down, Dir::Left, Dir::Up, Dir::Right];
+        for tick in 0..1200 {
+            assert!(!room.round_over, "ended early at tick {tick}");
+            room.queue_input("a", turns[tick % 4]);
+            room.queue_input("b", turns[tick % 4]);
+            room.step();
+        }
+        assert!(room.round_over);
+        assert_eq!(room.winner.as_deref(), Some("b"));
+        let standings = room.standings();
+        assert_eq!((standings[0].id.as_str(), standings[0].rank), ("b", 1));
+        assert_eq!((standings[1].id.as_str(), standings[1].rank), ("a", 2));
+    }
+
+    #[test]
+    fn round_result_is_sent_once() {
+        let mut room = room(ModeKind::TimeAttack, &[]);
+        let (tx, mut rx) = crate::outbox::channel(64, 64);
+        room.add_player("a".into(), "Ann".into(), tx);
+        room.round_ticks = 1199;
+        room.players.get_mut("a").unwrap().snake = vec![Cell(4, 4), Cell(3, 4), Cell(2, 4)];
+        room.step();
+        room.step();
+        let mut results = vec![];
+        while let Some(msg) = rx.try_recv() {
+            if let S2C::RoundResult { winner, standings } = msg {
+                results.push((winner, standings));
+            }
+        }
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.as_deref(), Some("a"));
+        assert_eq!(results[0].1[0].name, "Ann");
+    }
+}
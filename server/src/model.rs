@@ -1,5 +1,10 @@
+use crate::bot::BotTier;
 use serde::{Deserialize, Serialize};
 
+/// Bumped on breaking protocol changes. Clients may send it as the `v` query
+/// parameter; a mismatch is refused with `DisconnectReason::VersionMismatch`.
+pub const PROTOCOL_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Dir {
     Up,
@@ -11,6 +16,55 @@ pub enum Dir {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Cell(pub i32, pub i32);
 
+/// Rules a room is played with; picked by whoever creates the room.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ModeKind {
+    #[default]
+    #[serde(rename = "classic")]
+    Classic,
+    /// Last snake standing: no respawns, the arena shrinks over time.
+    #[serde(rename = "royale")]
+    BattleRoyale,
+    /// Fixed-length rounds; deaths cost points, best score at the buzzer wins.
+    #[serde(rename = "time_attack")]
+    TimeAttack,
+    /// Players split into teams that share a score.
+    #[serde(rename = "team")]
+    Team,
+}
+
+/// Playable area of the board, inclusive on both corners.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Arena {
+    pub min: Cell,
+    pub max: Cell,
+}
+
+impl Arena {
+    pub fn full(grid_w: i32, grid_h: i32) -> Self {
+        Self {
+            min: Cell(0, 0),
+            max: Cell(grid_w - 1, grid_h - 1),
+        }
+    }
+
+    pub fn contains(&self, c: Cell) -> bool {
+        c.0 >= self.min.0 && c.0 <= self.max.0 && c.1 >= self.min.1 && c.1 <= self.max.1
+    }
+
+    /// Pull every edge in by one cell, unless that would leave less than `min_size` per side.
+    pub fn shrink(&mut self, min_size: i32) -> bool {
+        let w = self.max.0 - self.min.0 + 1;
+        let h = self.max.1 - self.min.1 + 1;
+        if w - 2 < min_size || h - 2 < min_size {
+            return false;
+        }
+        self.min = Cell(self.min.0 + 1, self.min.1 + 1);
+        self.max = Cell(self.max.0 - 1, self.max.1 - 1);
+        true
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerSnapshot {
     pub id: String,
@@ -19,6 +73,29 @@ pub struct PlayerSnapshot {
     pub score: u32,
     pub lives: u32,
     pub body: Vec<Cell>,
+    /// Team index in team mode.
+    pub team: Option<u8>,
+    /// Server-side bot rather than a connected client.
+    pub bot: bool,
+    /// Smoothed round-trip time as measured by the server; `None` for bots
+    /// and until the first ping comes back.
+    pub ping_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamScore {
+    pub team: u8,
+    pub score: u32,
+}
+
+/// One row of the final standings sent with `S2C::RoundResult`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankEntry {
+    pub id: String,
+    pub name: String,
+    /// 1-based; tied players share a rank.
+    pub rank: u32,
+    pub score: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +113,43 @@ pub enum C2S {
     Start,
     #[serde(rename = "restart")]
     Restart,
+    /// Room owner only.
+    #[serde(rename = "add_bot")]
+    AddBot { tier: BotTier },
+    /// Room owner only.
+    #[serde(rename = "remove_bot")]
+    RemoveBot { id: String },
+}
+
+impl C2S {
+    /// Wire names of every message type, in `kind_index` order.
+    pub const KINDS: [&'static str; 8] = [
+        "join",
+        "input",
+        "ping",
+        "respawn",
+        "start",
+        "restart",
+        "add_bot",
+        "remove_bot",
+    ];
+
+    pub fn kind_index(&self) -> usize {
+        match self {
+            C2S::Join { .. } => 0,
+            C2S::Input { .. } => 1,
+            C2S::Ping { .. } => 2,
+            C2S::Respawn => 3,
+            C2S::Start => 4,
+            C2S::Restart => 5,
+            C2S::AddBot { .. } => 6,
+            C2S::RemoveBot { .. } => 7,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        Self::KINDS[self.kind_index()]
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +160,7 @@ pub enum S2C {
         player_id: String,
         grid: (i32, i32),
         tick_hz: u32,
+        mode: ModeKind,
     },
     #[serde(rename = "state")]
     State {
@@ -53,7 +168,129 @@ pub enum S2C {
         started: bool,
         food: Cell,
         players: Vec<PlayerSnapshot>,
+        arena: Arena,
+        round_over: bool,
+        /// Set once a round has a single survivor (battle royale).
+        winner: Option<String>,
+        /// Time left on the round clock, for modes that have one.
+        time_left_ms: Option<u64>,
+        /// Shared team scores; empty outside team mode.
+        teams: Vec<TeamScore>,
+    },
+    /// Sent once when a round ends.
+    #[serde(rename = "round_result")]
+    RoundResult {
+        winner: Option<String>,
+        standings: Vec<RankEntry>,
     },
     #[serde(rename = "pong")]
     Pong { t: u64 },
+    /// A message from the server operator, shown to players as-is.
+    #[serde(rename = "announcement")]
+    Announcement { text: String },
+    /// Last message before the server closes the connection.
+    #[serde(rename = "disconnect")]
+    Disconnect { reason: DisconnectReason },
+    /// A request was refused; the connection stays up.
+    #[serde(rename = "error")]
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// Type of the request this answers, when known.
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+    },
+}
+
+/// Machine-readable part of `S2C::Error`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadMessage,
+    TooLarge,
+    RateLimited,
+    QueueFull,
+    NotOwner,
+    NoSuchBot,
+    RoomFull,
+    Draining,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 8] = [
+        ErrorCode::BadMessage,
+        ErrorCode::TooLarge,
+        ErrorCode::RateLimited,
+        ErrorCode::QueueFull,
+        ErrorCode::NotOwner,
+        ErrorCode::NoSuchBot,
+        ErrorCode::RoomFull,
+        ErrorCode::Draining,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadMessage => "bad_message",
+            ErrorCode::TooLarge => "too_large",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::QueueFull => "queue_full",
+            ErrorCode::NotOwner => "not_owner",
+            ErrorCode::NoSuchBot => "no_such_bot",
+            ErrorCode::RoomFull => "room_full",
+            ErrorCode::Draining => "draining",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectReason {
+    ServerShutdown,
+    Kicked,
+    RoomClosed,
+    /// Nothing heard from the client for `Limits::idle_timeout_secs`.
+    IdleTimeout,
+    VersionMismatch,
+    RoomFull,
+    /// The server is at its room limit and can't open another.
+    ServerFull,
+    /// Kept sending more than the server accepts.
+    RateLimited,
+    /// Couldn't keep up with the server's updates.
+    SlowClient,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_use_their_short_wire_names() {
+        let names: Vec<String> = [
+            ModeKind::Classic,
+            ModeKind::BattleRoyale,
+            ModeKind::TimeAttack,
+            ModeKind::Team,
+        ]
+        .iter()
+        .map(|m| serde_json::to_string(m).unwrap())
+        .collect();
+        assert_eq!(
+            names,
+            ["\"classic\"", "\"royale\"", "\"time_attack\"", "\"team\""]
+        );
+        assert_eq!(ModeKind::default(), ModeKind::Classic);
+    }
+
+    #[test]
+    fn arena_shrinks_to_its_floor_and_no_further() {
+        let mut arena = Arena::full(8, 8);
+        assert!(arena.contains(Cell(0, 7)));
+        assert!(arena.shrink(4));
+        assert_eq!((arena.min, arena.max), (Cell(1, 1), Cell(6, 6)));
+        assert!(!arena.contains(Cell(0, 7)));
+        assert!(arena.shrink(4));
+        assert!(!arena.shrink(4));
+        assert_eq!((arena.min, arena.max), (Cell(2, 2), Cell(5, 5)));
+    }
 }
@@ -0,0 +1,194 @@
+//! Per-connection outbound queue.
+//!
+//! Control messages (`Hello`, `Pong`, `RoundResult`, `Disconnect`, ...) are
+//! delivered in order. `State` snapshots coalesce: a new one replaces any
+//! still waiting, so a stalled client costs one snapshot, not one per tick.
+//! A client that lets too many snapshots go stale in a row, or lets control
+//! messages pile up past the queue's capacity, is cut off with `SlowClient`.
+
+use crate::model::{DisconnectReason, S2C};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// `send` failed because the client was already cut off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CutOff;
+
+/// A queue holding at most `capacity` control messages plus one snapshot.
+/// The client is dropped once `max_lag` snapshots in a row were replaced
+/// before it took them.
+pub fn channel(capacity: usize, max_lag: u32) -> (Outbox, OutboxReceiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Queue::default()),
+        ready: Notify::new(),
+        cut_off: Notify::new(),
+        capacity: capacity.max(1),
+        max_lag: max_lag.max(1),
+    });
+    (
+        Outbox {
+            shared: shared.clone(),
+        },
+        OutboxReceiver { shared },
+    )
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Notify,
+    cut_off: Notify,
+    capacity: usize,
+    max_lag: u32,
+}
+
+#[derive(Default)]
+struct Queue {
+    msgs: VecDeque<S2C>,
+    /// Snapshots replaced since the receiver last took one.
+    lag: u32,
+    cut_off: bool,
+}
+
+/// Sending half; cheap to clone. Each player in a room holds one.
+#[derive(Clone)]
+pub struct Outbox {
+    shared: Arc<Shared>,
+}
+
+impl Outbox {
+    pub fn send(&self, msg: S2C) -> Result<(), CutOff> {
+        let mut q = self.shared.queue.lock().unwrap();
+        if q.cut_off {
+            return Err(CutOff);
+        }
+        if matches!(msg, S2C::State { .. }) {
+            if let Some(stale) = q.msgs.iter().position(|m| matches!(m, S2C::State { .. })) {
+                q.msgs.remove(stale);
+                q.lag += 1;
+            }
+        }
+        q.msgs.push_back(msg);
+        let control = q
+            .msgs
+            .iter()
+            .filter(|m| !matches!(m, S2C::State { .. }))
+            .count();
+        if q.lag >= self.shared.max_lag || control > self.shared.capacity {
+            // whatever was waiting is moot; leave only the reason
+            q.msgs.clear();
+            q.msgs.push_back(S2C::Disconnect {
+                reason: DisconnectReason::SlowClient,
+            });
+            q.cut_off = true;
+            self.shared.cut_off.notify_one();
+        }
+        drop(q);
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+
+    /// Messages waiting to be written.
+    pub fn depth(&self) -> usize {
+        self.shared.queue.lock().unwrap().msgs.len()
+    }
+
+    /// Resolves once the client has been cut off for lagging.
+    pub async fn cut_off(&self) {
+        loop {
+            if self.shared.queue.lock().unwrap().cut_off {
+                return;
+            }
+            self.shared.cut_off.notified().await;
+        }
+    }
+}
+
+/// Receiving half, drained by the connection's writer.
+pub struct OutboxReceiver {
+    shared: Arc<Shared>,
+}
+
+impl OutboxReceiver {
+    pub async fn recv(&mut self) -> S2C {
+        loop {
+            if let Some(msg) = self.try_recv() {
+                return msg;
+            }
+            self.shared.ready.notified().await;
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<S2C> {
+        let mut q = self.shared.queue.lock().unwrap();
+        let msg = q.msgs.pop_front()?;
+        if matches!(msg, S2C::State { .. }) {
+            q.lag = 0;
+        }
+        Some(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Arena, Cell};
+
+    fn state(seq: u64) -> S2C {
+        S2C::State {
+            seq,
+            started: true,
+            food: Cell(0, 0),
+            players: vec![],
+            arena: Arena::full(4, 4),
+            round_over: false,
+            winner: None,
+            time_left_ms: None,
+            teams: vec![],
+        }
+    }
+
+    fn drain(rx: &mut OutboxReceiver) -> Vec<String> {
+        std::iter::from_fn(|| rx.try_recv())
+            .map(|m| match m {
+                S2C::State { seq, .. } => format!("state {seq}"),
+                S2C::Pong { t } => format!("pong {t}"),
+                S2C::Disconnect { reason } => format!("{reason:?}"),
+                other => format!("{other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn snapshots_coalesce_behind_ordered_control_messages() {
+        let (tx, mut rx) = channel(8, 10);
+        tx.send(state(1)).unwrap();
+        tx.send(S2C::Pong { t: 1 }).unwrap();
+        tx.send(state(2)).unwrap();
+        tx.send(S2C::Pong { t: 2 }).unwrap();
+        tx.send(state(3)).unwrap();
+        assert_eq!(tx.depth(), 3);
+        assert_eq!(drain(&mut rx), ["pong 1", "pong 2", "state 3"]);
+    }
+
+    #[test]
+    fn stalled_clients_are_cut_off() {
+        let (tx, mut rx) = channel(8, 3);
+        for seq in 1..=3 {
+            tx.send(state(seq)).unwrap();
+        }
+        // taking a snapshot resets the count
+        assert_eq!(drain(&mut rx), ["state 3"]);
+        for seq in 4..=7 {
+            tx.send(state(seq)).unwrap();
+        }
+        assert_eq!(drain(&mut rx), ["SlowClient"]);
+        assert_eq!(tx.send(S2C::Pong { t: 1 }), Err(CutOff));
+
+        let (tx, mut rx) = channel(2, 3);
+        for t in 0..3 {
+            tx.send(S2C::Pong { t }).unwrap();
+        }
+        assert_eq!(drain(&mut rx), ["SlowClient"]);
+    }
+}
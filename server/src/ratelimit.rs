@@ -0,0 +1,170 @@
+//! Per-connection flood protection.
+//!
+//! Each client message type gets its own token bucket; messages over budget
+//! are dropped and count as a violation, as do oversized frames. Frames that
+//! don't parse have a bucket of their own: a few are mistakes, more are a
+//! flood (see `ProtocolError::is_violation`).
+//! Violations refill slowly, so only a client that keeps it up gets cut off.
+
+use crate::config::Limits;
+use crate::error::ProtocolError;
+use crate::model::C2S;
+use std::time::{Duration, Instant};
+
+/// Burst size and refill per second for each entry of `C2S::KINDS`.
+const RATES: [(f64, f64); C2S::KINDS.len()] = [
+    (2.0, 0.2),   // join
+    (40.0, 20.0), // input
+    (40.0, 10.0), // ping
+    (5.0, 1.0),   // respawn
+    (5.0, 1.0),   // start
+    (5.0, 1.0),   // restart
+    (10.0, 2.0),  // add_bot
+    (10.0, 2.0),  // remove_bot
+];
+
+/// Burst size and refill per second for frames that don't parse.
+const MALFORMED_RATE: (f64, f64) = (5.0, 1.0);
+
+/// Violations allowed in a burst refill over this long.
+const STRIKE_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// Starts full.
+    pub fn new(capacity: f64, per_sec: f64, now: Instant) -> Self {
+        Self {
+            capacity,
+            per_sec,
+            tokens: capacity,
+            last: now,
+        }
+    }
+
+    /// Take one token if there is one.
+    pub fn take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// One connection's budgets and strike count.
+#[derive(Debug, Clone)]
+pub struct InboundLimiter {
+    buckets: [TokenBucket; C2S::KINDS.len()],
+    malformed: TokenBucket,
+    strikes: TokenBucket,
+    /// Violations so far, for logging.
+    pub violations: u64,
+}
+
+impl InboundLimiter {
+    pub fn new(limits: &Limits, now: Instant) -> Self {
+        let max = limits.max_violations.max(1) as f64;
+        Self {
+            buckets: std::array::from_fn(|i| TokenBucket::new(RATES[i].0, RATES[i].1, now)),
+            malformed: TokenBucket::new(MALFORMED_RATE.0, MALFORMED_RATE.1, now),
+            strikes: TokenBucket::new(max, max / STRIKE_WINDOW.as_secs_f64(), now),
+            violations: 0,
+        }
+    }
+
+    /// Whether `msg` fits its type's budget.
+    pub fn allow(&mut self, msg: &C2S, now: Instant) -> Result<(), ProtocolError> {
+        if self.buckets[msg.kind_index()].take(now) {
+            Ok(())
+        } else {
+            Err(ProtocolError::RateLimited(msg.kind()))
+        }
+    }
+
+    /// Charge a frame that didn't parse, with the parser's complaint.
+    pub fn malformed(&mut self, error: String, now: Instant) -> ProtocolError {
+        if self.malformed.take(now) {
+            ProtocolError::Malformed(error)
+        } else {
+            ProtocolError::TooManyMalformed
+        }
+    }
+
+    /// Record a violation; true once the client has run out of allowance.
+    pub fn strike(&mut self, now: Instant) -> bool {
+        self.violations += 1;
+        !self.strikes.take(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills() {
+        let t0 = Instant::now();
+        let mut b = TokenBucket::new(3.0, 2.0, t0);
+        assert!((0..3).all(|_| b.take(t0)));
+        assert!(!b.take(t0));
+        assert!(!b.take(t0 + Duration::from_millis(400)));
+        assert!(b.take(t0 + Duration::from_millis(500)));
+        // never holds more than its capacity
+        let later = t0 + Duration::from_secs(60);
+        assert!((0..3).all(|_| b.take(later)));
+        assert!(!b.take(later));
+    }
+
+    #[test]
+    fn flooding_one_type_leaves_others_alone_until_cut_off() {
+        let t0 = Instant::now();
+        let limits = Limits {
+            max_violations: 5,
+            ..Default::default()
+        };
+        let mut l = InboundLimiter::new(&limits, t0);
+        let start = C2S::Start;
+        let mut cut_off = false;
+        for _ in 0..20 {
+            if let Err(v) = l.allow(&start, t0) {
+                assert_eq!(v, ProtocolError::RateLimited("start"));
+                if l.strike(t0) {
+                    cut_off = true;
+                    break;
+                }
+            }
+        }
+        assert!(cut_off);
+        assert_eq!(l.violations, 6);
+        assert!(l.allow(&C2S::Ping { t: 1 }, t0).is_ok());
+    }
+
+    #[test]
+    fn a_few_malformed_frames_are_mistakes_and_more_are_a_flood() {
+        let t0 = Instant::now();
+        let mut l = InboundLimiter::new(&Limits::default(), t0);
+        for _ in 0..5 {
+            assert!(!l.malformed("eof".into(), t0).is_violation());
+        }
+        assert_eq!(
+            l.malformed("eof".into(), t0),
+            ProtocolError::TooManyMalformed
+        );
+        let later = t0 + Duration::from_secs(1);
+        assert_eq!(
+            l.malformed("eof".into(), later),
+            ProtocolError::Malformed("eof".into())
+        );
+    }
+}
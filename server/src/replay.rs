@@ -0,0 +1,144 @@
+//! Match recordings for `Config::replay_dir`.
+//!
+//! Each room writes one file, `<room>-<unix ms>.jsonl`, holding every
+//! `State` it broadcast and every `RoundResult`, one JSON message per line,
+//! exactly as clients received them. The file lives on a thread of its own:
+//! the ticker only hands messages over a bounded channel, so a slow disk
+//! costs recorded ticks rather than game ticks.
+
+use crate::model::S2C;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Messages waiting for the writer before new ones are dropped.
+const QUEUE: usize = 256;
+/// Written data reaches the file at least this often.
+const FLUSH_EVERY: Duration = Duration::from_secs(1);
+/// A recording stops once its file reaches this size (about 15 hours of a
+/// busy room at 10 Hz).
+const MAX_FILE_BYTES: u64 = 512 << 20;
+
+pub struct ReplayWriter {
+    tx: SyncSender<S2C>,
+    /// Messages lost to a full queue; only the first one is logged.
+    dropped: u64,
+}
+
+impl ReplayWriter {
+    /// Start recording `room` into `dir`. The file is opened on the writer
+    /// thread; failures there are logged and the room plays on unrecorded.
+    pub fn spawn(dir: &Path, room: &str) -> io::Result<Self> {
+        let path = dir.join(file_name(room));
+        let room = room.to_string();
+        let (tx, rx) = mpsc::sync_channel(QUEUE);
+        std::thread::Builder::new()
+            .name(format!("replay-{room}"))
+            .spawn(move || {
+                let out = match File::create(&path) {
+                    Ok(f) => BufWriter::new(f),
+                    Err(e) => {
+                        warn!("replay: room={room} not recorded: {}: {e}", path.display());
+                        return;
+                    }
+                };
+                info!("replay: room={room} file={}", path.display());
+                if let Err(e) = write_all(out, &rx) {
+                    warn!("replay: {} stopped: {e}", path.display());
+                }
+            })?;
+        Ok(Self { tx, dropped: 0 })
+    }
+
+    /// Queue `msg` for the file; never blocks.
+    pub fn record(&mut self, msg: &S2C) {
+        match self.tx.try_send(msg.clone()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                if self.dropped == 0 {
+                    warn!("replay: writer is behind; dropping messages");
+                }
+                self.dropped += 1;
+            }
+            // the writer gave up and already said why
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+/// `<room>-<unix ms>.jsonl`, with the room name kept from escaping the directory.
+fn file_name(room: &str) -> String {
+    let safe: String = room
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    format!("{safe}-{ms}.jsonl")
+}
+
+/// Drain `rx` into `out` until the room goes away or the size cap is hit.
+fn write_all(mut out: impl Write, rx: &mpsc::Receiver<S2C>) -> io::Result<()> {
+    let mut written = 0u64;
+    let mut flushed = Instant::now();
+    loop {
+        if flushed.elapsed() >= FLUSH_EVERY {
+            out.flush()?;
+            flushed = Instant::now();
+        }
+        let msg = match rx.recv_timeout(FLUSH_EVERY) {
+            Ok(msg) => msg,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return out.flush(),
+        };
+        let mut line = serde_json::to_vec(&msg)?;
+        line.push(b'\n');
+        written += line.len() as u64;
+        if written > MAX_FILE_BYTES {
+            info!("replay: recording reached {MAX_FILE_BYTES} bytes; stopped");
+            return out.flush();
+        }
+        out.write_all(&line)?;
+        // a finished round is a natural checkpoint
+        if matches!(msg, S2C::RoundResult { .. }) {
+            out.flush()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_one_message_per_line_under_a_safe_name() {
+        let name = file_name("../lobby");
+        assert!(name.starts_with("___lobby-"), "{name}");
+        assert!(name.ends_with(".jsonl"), "{name}");
+
+        let (tx, rx) = mpsc::sync_channel(4);
+        tx.send(S2C::Pong { t: 1 }).unwrap();
+        tx.send(S2C::RoundResult {
+            winner: None,
+            standings: vec![],
+        })
+        .unwrap();
+        drop(tx);
+        let mut out = vec![];
+        write_all(&mut out, &rx).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("\"type\":\"round_result\""));
+    }
+}
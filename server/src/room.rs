@@ -1,10 +1,20 @@
-use crate::model::{Cell, Dir, PlayerSnapshot, S2C};
-use rand::{thread_rng, Rng};
+use crate::bot::{self, BotTier, BotView};
+use crate::clock::{self, SharedClock};
+use crate::config::Limits;
+use crate::error::ProtocolError;
+use crate::metrics::Histogram;
+use crate::mode::{self, GameMode, Standing};
+use crate::model::{Arena, Cell, Dir, DisconnectReason, ModeKind, PlayerSnapshot, RankEntry, S2C};
+use crate::outbox::Outbox;
+use crate::replay::ReplayWriter;
+use crate::rtt::RttEstimator;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, VecDeque},
+    net::SocketAddr,
     time::{Duration, Instant},
 };
-use tokio::sync::mpsc;
 
 #[derive(Clone)]
 pub struct Player {
@@ -16,105 +26,428 @@ pub struct Player {
     pub score: u32,
     pub lives: u32,
     pub pending: VecDeque<Dir>,
-    pub tx: mpsc::UnboundedSender<S2C>, // outbound to this player
+    pub team: Option<u8>,
+    /// Set for server-side bots, which pick their own moves each tick.
+    pub bot: Option<BotTier>,
+    pub tx: Option<Outbox>, // outbound to this player; bots have none
+    /// Where a networked player connected from.
+    pub addr: Option<SocketAddr>,
+    /// Measured by the connection from WebSocket pings; empty for bots.
+    pub rtt: RttEstimator,
+}
+
+/// Rule set chosen by whoever creates the room.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomRules {
+    pub mode: ModeKind,
+    /// Team mode: how many teams to split players into (2–4).
+    pub teams: u8,
+    /// Team mode: whether running into a teammate's body kills.
+    pub friendly_fire: bool,
+    /// Top the room up with bots until it has this many snakes.
+    pub fill_bots: u8,
+}
+
+impl Default for RoomRules {
+    fn default() -> Self {
+        Self {
+            mode: ModeKind::Classic,
+            teams: 2,
+            friendly_fire: false,
+            fill_bots: 0,
+        }
+    }
+}
+
+/// What happened during one `Room::advance`.
+#[derive(Debug, Default)]
+pub(crate) struct Tick {
+    pub eaten: Vec<String>,
+    pub deaths: Vec<String>,
+    /// This tick ended the round.
+    pub finished: bool,
 }
 
 pub struct Room {
     pub _name: String,
     pub grid_w: i32,
     pub grid_h: i32,
-    pub players: HashMap<String, Player>,
+    /// Ordered by id so a seeded room plays out the same way every time.
+    pub players: BTreeMap<String, Player>,
     pub food: Cell,
     pub seq: u64,
     last_tick: Instant,
     tick: Duration,
+    /// Source of `now` for tick scheduling; real time unless swapped out.
+    clock: SharedClock,
     pub started: bool,
+    /// Held by an admin: no ticks until resumed.
+    pub paused: bool,
+    /// Turns a player can have waiting; more are dropped.
+    pub input_queue_depth: usize,
+    /// Snakes (humans and bots) past which `add_bot` refuses.
+    pub max_snakes: usize,
+    rules: RoomRules,
+    /// Rules for this room; see `mode`.
+    mode: Box<dyn GameMode>,
+    pub arena: Arena,
+    /// Ticks played in the current round.
+    pub round_ticks: u64,
+    pub round_over: bool,
+    pub winner: Option<String>,
+    /// First human in the room; allowed to add and remove bots.
+    pub owner: Option<String>,
+    fill_bots: usize,
+    /// Bots added to reach `fill_bots`, dropped again as humans arrive.
+    fillers: Vec<String>,
+    bots_created: u32,
+    /// Drives spawns, food and bot moves; see `reseed`.
+    rng: StdRng,
+    /// Wall time spent in `step`, for `/metrics`.
+    pub(crate) tick_time: Histogram,
+    /// Where broadcasts are recorded, if the server keeps replays.
+    pub(crate) replay: Option<ReplayWriter>,
 }
 
 impl Room {
-    pub fn new(name: impl Into<String>, grid_w: i32, grid_h: i32, hz: u32) -> Self {
+    pub fn new(
+        name: impl Into<String>,
+        grid_w: i32,
+        grid_h: i32,
+        hz: u32,
+        rules: RoomRules,
+    ) -> Self {
+        let tick = Duration::from_millis((1000 / hz.max(1)) as u64);
+        let clock = clock::real();
         let mut room = Self {
             _name: name.into(),
             grid_w,
             grid_h,
-            players: HashMap::new(),
+            players: BTreeMap::new(),
             food: Cell(0, 0),
             seq: 0,
-            last_tick: Instant::now(),
-            tick: Duration::from_millis((1000 / hz.max(1)) as u64),
+            last_tick: clock.now(),
+            tick,
+            clock,
             started: false,
+            paused: false,
+            input_queue_depth: 2,
+            max_snakes: Limits::default().max_snakes_per_room,
+            rules,
+            mode: mode::create(&rules, tick),
+            arena: Arena::full(grid_w, grid_h),
+            round_ticks: 0,
+            round_over: false,
+            winner: None,
+            owner: None,
+            fill_bots: rules.fill_bots as usize,
+            fillers: vec![],
+            bots_created: 0,
+            rng: StdRng::from_entropy(),
+            tick_time: Histogram::default(),
+            replay: None,
         };
         room.food = room.random_empty();
         room
     }
 
-    fn random_empty(&self) -> Cell {
-        let mut rng = thread_rng();
+    /// Schedule ticks against `clock` instead of real time.
+    pub(crate) fn set_clock(&mut self, clock: SharedClock) {
+        self.last_tick = clock.now();
+        self.clock = clock;
+    }
+
+    /// Replace the room's random source, making spawns, food and bots reproducible.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn mode_kind(&self) -> ModeKind {
+        self.mode.kind()
+    }
+
+    /// A round that will end by itself is under way and people are playing it.
+    pub fn round_in_progress(&self) -> bool {
+        self.mode.has_round_end() && self.started && !self.round_over && self.humans() > 0
+    }
+
+    pub fn rules(&self) -> RoomRules {
+        self.rules
+    }
+
+    /// Switch rules mid-game: a fresh mode, bots topped up again and a new round.
+    pub fn set_rules(&mut self, rules: RoomRules) {
+        for id in std::mem::take(&mut self.fillers) {
+            self.players.remove(&id);
+        }
+        self.rules = rules;
+        self.fill_bots = rules.fill_bots as usize;
+        self.mode = mode::create(&rules, self.tick);
+        let ids: Vec<String> = self.players.keys().cloned().collect();
+        for id in ids {
+            if let Some(p) = self.players.get_mut(&id) {
+                p.team = None;
+            }
+            self.with_mode(|m, room| m.on_join(room, &id));
+        }
+        self.top_up_bots();
+        self.reset_round();
+    }
+
+    /// Run a mode hook with mutable access to the room. The mode is swapped out
+    /// for the duration so the hook can freely borrow `self`.
+    fn with_mode<R>(&mut self, f: impl FnOnce(&mut dyn GameMode, &mut Room) -> R) -> R {
+        let mut mode = std::mem::replace(&mut self.mode, Box::new(mode::Classic));
+        let out = f(mode.as_mut(), self);
+        self.mode = mode;
+        out
+    }
+
+    fn occupied(&self, c: Cell) -> bool {
+        self.players
+            .values()
+            .any(|p| p.alive && p.snake.contains(&c))
+    }
+
+    pub(crate) fn random_empty(&mut self) -> Cell {
+        let (min, max) = (self.arena.min, self.arena.max);
         for _ in 0..1000 {
-            let c = Cell(rng.gen_range(0..self.grid_w), rng.gen_range(0..self.grid_h));
-            if !self.players.values().any(|p| p.snake.contains(&c)) {
+            let c = Cell(
+                self.rng.gen_range(min.0..=max.0),
+                self.rng.gen_range(min.1..=max.1),
+            );
+            if !self.occupied(c) {
                 return c;
             }
         }
-        Cell(0, 0)
+        min
     }
 
-    pub fn add_player(&mut self, id: String, name: String, tx: mpsc::UnboundedSender<S2C>) {
-        let mid = Cell(self.grid_w / 2, self.grid_h / 2);
-        let snake = vec![mid, Cell(mid.0 - 1, mid.1), Cell(mid.0 - 2, mid.1)];
+    /// Pick a spawn spot: the centre when it's free, otherwise any row with
+    /// room for a 3-cell body heading right plus a little space in front.
+    fn spawn_body(&mut self) -> Vec<Cell> {
+        let fits = |room: &Room, head: Cell| {
+            (-2..=3).all(|dx| {
+                let c = Cell(head.0 + dx, head.1);
+                room.arena.contains(c) && !room.occupied(c)
+            })
+        };
+        let mut head = Cell(self.grid_w / 2, self.grid_h / 2);
+        if !fits(self, head) {
+            let (min, max) = (self.arena.min, self.arena.max);
+            for _ in 0..1000 {
+                let c = Cell(
+                    self.rng.gen_range(min.0..=max.0),
+                    self.rng.gen_range(min.1..=max.1),
+                );
+                if fits(self, c) {
+                    head = c;
+                    break;
+                }
+            }
+        }
+        vec![head, Cell(head.0 - 1, head.1), Cell(head.0 - 2, head.1)]
+    }
+
+    pub fn add_player(&mut self, id: String, name: String, tx: Outbox) {
+        if self.owner.is_none() {
+            self.owner = Some(id.clone());
+        }
+        self.insert_player(id, name, Some(tx), None);
+        // make space for the newcomer, or fill up if this is the first human
+        if let Some(filler) = self.fillers.pop() {
+            self.players.remove(&filler);
+        }
+        self.top_up_bots();
+    }
+
+    /// Add a server-side bot and return its id, unless the room already
+    /// holds `max_snakes`.
+    pub fn add_bot(&mut self, tier: BotTier) -> Result<String, ProtocolError> {
+        if self.players.len() >= self.max_snakes {
+            return Err(ProtocolError::RoomFull {
+                max: self.max_snakes,
+            });
+        }
+        self.bots_created += 1;
+        let id = format!("bot-{}", self.bots_created);
+        let name = format!("{} #{}", tier.label(), self.bots_created);
+        self.insert_player(id.clone(), name, None, Some(tier));
+        Ok(id)
+    }
+
+    /// Remove a bot; returns false if `id` is not a bot in this room.
+    pub fn remove_bot(&mut self, id: &str) -> bool {
+        if self.players.get(id).is_none_or(|p| p.bot.is_none()) {
+            return false;
+        }
+        self.players.remove(id);
+        self.fillers.retain(|f| f != id);
+        true
+    }
+
+    pub fn humans(&self) -> usize {
+        self.players.values().filter(|p| p.bot.is_none()).count()
+    }
+
+    fn top_up_bots(&mut self) {
+        while self.players.len() < self.fill_bots && self.humans() > 0 {
+            let tier = BotTier::ALL[self.fillers.len() % BotTier::ALL.len()];
+            let Ok(id) = self.add_bot(tier) else {
+                break;
+            };
+            self.fillers.push(id);
+        }
+    }
+
+    pub(crate) fn insert_player(
+        &mut self,
+        id: String,
+        name: String,
+        tx: Option<Outbox>,
+        bot: Option<BotTier>,
+    ) {
         let player = Player {
             id: id.clone(),
             _name: name,
-            snake,
+            snake: vec![],
             dir: Dir::Right,
-            alive: true,
+            alive: false,
             score: 0,
             lives: 3,
             pending: VecDeque::new(),
+            team: None,
+            bot,
             tx,
+            addr: None,
+            rtt: RttEstimator::default(),
         };
-        self.players.insert(id, player);
+        self.players.insert(id.clone(), player);
+        self.with_mode(|m, room| m.on_join(room, &id));
     }
 
     pub fn remove_player(&mut self, id: &str) {
         self.players.remove(id);
+        if self.owner.as_deref() == Some(id) {
+            self.owner = self
+                .players
+                .values()
+                .find(|p| p.bot.is_none())
+                .map(|p| p.id.clone());
+        }
+        if self.humans() == 0 {
+            // nobody left to watch: park the bots instead of ticking forever
+            self.started = false;
+        } else {
+            self.top_up_bots();
+        }
     }
-    pub fn respawn_player(&mut self, id: &str) {
+
+    /// Put a player's snake on the board at a fresh spawn point.
+    pub(crate) fn place_player(&mut self, id: &str) {
+        let body = self.spawn_body();
         if let Some(p) = self.players.get_mut(id) {
-            if p.lives > 0 {
-                let mid = Cell(self.grid_w / 2, self.grid_h / 2);
-                p.snake = vec![mid, Cell(mid.0 - 1, mid.1), Cell(mid.0 - 2, mid.1)];
-                p.dir = Dir::Right;
-                p.alive = true;
-                p.pending.clear();
-            }
+            p.snake = body;
+            p.dir = Dir::Right;
+            p.alive = true;
+            p.pending.clear();
         }
     }
-    pub fn queue_input(&mut self, id: &str, d: Dir) {
-        if let Some(p) = self.players.get_mut(id) {
-            // prevent 180° reversals
-            let illegal = matches!(
-                (p.dir, d),
-                (Dir::Up, Dir::Down)
-                    | (Dir::Down, Dir::Up)
-                    | (Dir::Left, Dir::Right)
-                    | (Dir::Right, Dir::Left)
-            );
-            if !illegal {
-                p.pending.push_back(d);
-            }
+
+    pub fn respawn_player(&mut self, id: &str) {
+        if self.mode.can_respawn(self, id) {
+            self.place_player(id);
+        }
+    }
+
+    pub fn restart_player(&mut self, id: &str) {
+        self.with_mode(|m, room| m.on_restart(room, id));
+    }
+
+    pub fn start(&mut self) {
+        if self.round_over {
+            self.reset_round();
         }
+        self.started = true;
+    }
+
+    /// Open the arena back up, clear the round state and let the mode seat everyone again.
+    pub(crate) fn reset_round(&mut self) {
+        self.arena = Arena::full(self.grid_w, self.grid_h);
+        self.round_ticks = 0;
+        self.round_over = false;
+        self.winner = None;
+        for p in self.players.values_mut() {
+            p.alive = false;
+            p.score = 0;
+            p.lives = 3;
+        }
+        self.with_mode(|m, room| m.on_round_start(room));
+        self.food = self.random_empty();
+    }
+
+    /// Queue a turn for the next ticks, one per tick. Turns are checked
+    /// against the last queued direction, so fast presses can't line up a
+    /// 180° into the snake's own neck; repeats of it are dropped. Returns false
+    /// only if the player's queue is already full.
+    pub fn queue_input(&mut self, id: &str, d: Dir) -> bool {
+        let Some(p) = self.players.get_mut(id) else {
+            return true;
+        };
+        let last = p.pending.back().copied().unwrap_or(p.dir);
+        let reversal = matches!(
+            (last, d),
+            (Dir::Up, Dir::Down)
+                | (Dir::Down, Dir::Up)
+                | (Dir::Left, Dir::Right)
+                | (Dir::Right, Dir::Left)
+        );
+        if d == last || reversal {
+            return true;
+        }
+        if p.pending.len() >= self.input_queue_depth {
+            return false;
+        }
+        p.pending.push_back(d);
+        true
     }
 
     pub fn tick_due(&self) -> bool {
-        self.started && self.last_tick.elapsed() >= self.tick
+        self.started
+            && !self.paused
+            && self.clock.now().saturating_duration_since(self.last_tick) >= self.tick
     }
 
     pub fn step(&mut self) {
-        self.last_tick = Instant::now();
+        // real time on purpose: this measures work, not the simulated clock
+        let started = Instant::now();
+        self.last_tick = self.clock.now();
+        let tick = self.advance();
+        self.broadcast();
+        if tick.finished {
+            let result = self.round_result(&self.standings());
+            self.send_all(&result);
+            if let Some(replay) = &mut self.replay {
+                replay.record(&result);
+            }
+        }
+        self.tick_time.observe(started.elapsed());
+    }
+
+    /// Play one tick of the simulation without touching the clock or any
+    /// channel.
+    pub(crate) fn advance(&mut self) -> Tick {
         self.seq += 1;
         tracing::debug!("tick seq={} players={}", self.seq, self.players.len());
 
+        // round decided: hold the final board until someone restarts
+        if self.round_over {
+            return Tick::default();
+        }
+        self.round_ticks += 1;
+        self.drive_bots();
+
         // apply one queued dir per player (keeps latency small but stable)
         for p in self.players.values_mut() {
             if let Some(d) = p.pending.pop_front() {
@@ -123,7 +456,7 @@ impl Room {
         }
 
         // compute next heads
-        let mut next_heads: HashMap<String, Cell> = HashMap::new();
+        let mut next_heads: BTreeMap<String, Cell> = BTreeMap::new();
         for p in self.players.values() {
             if !p.alive {
                 continue;
@@ -138,23 +471,27 @@ impl Room {
             next_heads.insert(p.id.clone(), head);
         }
 
-        // pre-collect all occupied cells for body collisions
-        let all_body: Vec<Cell> = self
+        // pre-collect all occupied cells (and whose they are) for body collisions
+        let all_body: Vec<(Cell, &str)> = self
             .players
             .values()
-            .flat_map(|p| if p.alive { p.snake.clone() } else { vec![] })
+            .filter(|p| p.alive)
+            .flat_map(|p| p.snake.iter().map(move |c| (*c, p.id.as_str())))
             .collect();
 
-        // mark deaths: wall, body, head-to-head
+        // mark deaths: wall/storm, body, head-to-head
         let mut deaths: Vec<String> = vec![];
 
-        // wall & body
+        // wall & body (outside the arena is either off the board or in the storm)
         for (id, head) in &next_heads {
-            if head.0 < 0 || head.0 >= self.grid_w || head.1 < 0 || head.1 >= self.grid_h {
+            if !self.arena.contains(*head) {
                 deaths.push(id.clone());
                 continue;
             }
-            if all_body.contains(head) {
+            if all_body
+                .iter()
+                .any(|(c, owner)| c == head && self.mode.body_hit_is_lethal(self, id, owner))
+            {
                 deaths.push(id.clone());
                 continue;
             }
@@ -171,6 +508,7 @@ impl Room {
         }
 
         // apply moves for survivors
+        let mut eaten: Vec<String> = vec![];
         for (id, head) in next_heads {
             if deaths.contains(&id) {
                 continue;
@@ -178,7 +516,7 @@ impl Room {
             if let Some(p) = self.players.get_mut(&id) {
                 p.snake.insert(0, head);
                 if head == self.food {
-                    p.score += 1;
+                    eaten.push(id);
                     self.food = self.random_empty();
                 } else {
                     p.snake.pop();
@@ -186,42 +524,157 @@ impl Room {
             }
         }
 
-        // finalize deaths
-        for id in deaths {
-            if let Some(p) = self.players.get_mut(&id) {
-                p.alive = false;
-                if p.lives > 0 {
-                    p.lives -= 1;
+        // let the mode score food and settle deaths
+        deaths.sort();
+        deaths.dedup();
+        self.with_mode(|m, room| {
+            for id in &eaten {
+                m.on_eat(room, id);
+            }
+            for id in &deaths {
+                if let Some(p) = room.players.get_mut(id) {
+                    p.alive = false;
                 }
+                m.on_death(room, id);
+            }
+            m.on_tick(room);
+        });
+
+        if self.mode.is_round_over(self) {
+            self.round_over = true;
+            let standings = self.standings();
+            self.winner = mode::sole_winner(&standings);
+            tracing::info!("round over: room={} winner={:?}", self._name, self.winner);
+            return Tick {
+                eaten,
+                deaths,
+                finished: true,
+            };
+        }
+        Tick {
+            eaten,
+            deaths,
+            finished: false,
+        }
+    }
+
+    fn round_result(&self, standings: &[Standing]) -> S2C {
+        let standings = standings
+            .iter()
+            .map(|s| RankEntry {
+                id: s.id.clone(),
+                name: self
+                    .players
+                    .get(&s.id)
+                    .map(|p| p._name.clone())
+                    .unwrap_or_default(),
+                rank: s.rank,
+                score: s.score,
+            })
+            .collect();
+        S2C::RoundResult {
+            winner: self.winner.clone(),
+            standings,
+        }
+    }
+
+    /// Let every bot pick this tick's move, and bring dead bots back where the mode allows.
+    fn drive_bots(&mut self) {
+        // borrowed out so the views below can hold `&self`
+        let mut rng = std::mem::replace(&mut self.rng, StdRng::seed_from_u64(0));
+        let mut moves: Vec<(String, Dir)> = vec![];
+        let mut dead: Vec<String> = vec![];
+        for p in self.players.values() {
+            let Some(tier) = p.bot else {
+                continue;
+            };
+            if !p.alive {
+                dead.push(p.id.clone());
+                continue;
+            }
+            let view = self.bot_view(p);
+            moves.push((p.id.clone(), bot::choose(tier, &view, &mut rng)));
+        }
+        self.rng = rng;
+        for (id, d) in moves {
+            self.queue_input(&id, d);
+        }
+        for id in dead {
+            if self.mode.can_respawn(self, &id) {
+                self.place_player(&id);
+            } else {
+                self.restart_player(&id);
+            }
+        }
+    }
+
+    /// Steer `id` with `pick` for this tick, or bring it back if it died, the
+    /// way `drive_bots` handles built-in bots. For players whose moves come
+    /// from outside the room, such as tournament entrants.
+    pub(crate) fn drive_with(
+        &mut self,
+        id: &str,
+        pick: impl FnOnce(&BotView<'_>, &mut StdRng) -> Dir,
+    ) {
+        let mut rng = std::mem::replace(&mut self.rng, StdRng::seed_from_u64(0));
+        let d = match self.players.get(id) {
+            Some(p) if p.alive => Some(pick(&self.bot_view(p), &mut rng)),
+            _ => None,
+        };
+        self.rng = rng;
+        match d {
+            Some(d) => {
+                self.queue_input(id, d);
             }
+            None if !self.players.contains_key(id) => {}
+            None if self.mode.can_respawn(self, id) => self.place_player(id),
+            None => self.restart_player(id),
         }
+    }
 
-        // auto-respawn if all remaining-with-lives are dead (solo-friendly)
-        let any_with_lives = self.players.values().any(|p| p.lives > 0);
-        if any_with_lives
-            && self
+    pub fn bot_view<'a>(&'a self, p: &'a Player) -> BotView<'a> {
+        BotView {
+            arena: self.arena,
+            food: self.food,
+            me: &p.snake,
+            dir: p.pending.back().copied().unwrap_or(p.dir),
+            others: self
                 .players
                 .values()
-                .filter(|p| p.lives > 0)
-                .all(|p| !p.alive)
-        {
-            for id in self.players.keys().cloned().collect::<Vec<_>>() {
-                if let Some(p) = self.players.get(&id) {
-                    if p.lives == 0 {
-                        continue;
-                    }
-                }
-                self.respawn_player(&id);
-            }
+                .filter(|o| o.alive && o.id != p.id)
+                .map(|o| o.snake.as_slice())
+                .collect(),
+        }
+    }
+
+    /// Final or running standings according to the room's mode.
+    pub fn standings(&self) -> Vec<Standing> {
+        self.mode.scoring(self)
+    }
+
+    // broadcast snapshot for this tick
+    fn broadcast(&mut self) {
+        let snapshot = self.snapshot();
+        self.send_all(&snapshot);
+        if let Some(replay) = &mut self.replay {
+            replay.record(&snapshot);
         }
+    }
 
-        // broadcast snapshot for this tick
-        let msg = self.snapshot();
+    /// Send `msg` to every connected player.
+    pub fn send_all(&self, msg: &S2C) {
         for p in self.players.values() {
-            let _ = p.tx.send(msg.clone());
+            if let Some(tx) = &p.tx {
+                let _ = tx.send(msg.clone());
+            }
         }
     }
 
+    /// Send every connected player a `Disconnect`; their connections close after it.
+    pub fn disconnect_all(&self, reason: DisconnectReason) {
+        self.send_all(&S2C::Disconnect { reason });
+    }
+
     // Build a full-state snapshot message
     pub fn snapshot(&self) -> S2C {
         let players = self
@@ -234,6 +687,9 @@ impl Room {
                 score: p.score,
                 lives: p.lives,
                 body: p.snake.clone(),
+                team: p.team,
+                bot: p.bot.is_some(),
+                ping_ms: p.rtt.rtt_ms(),
             })
             .collect::<Vec<_>>();
 
@@ -242,6 +698,98 @@ impl Room {
             started: self.started,
             food: self.food,
             players,
+            arena: self.arena,
+            round_over: self.round_over,
+            winner: self.winner.clone(),
+            time_left_ms: self
+                .mode
+                .ticks_left(self)
+                .map(|t| t * self.tick.as_millis() as u64),
+            teams: self.mode.team_scores(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bot::BotTier;
+    use crate::error::ProtocolError;
+    use crate::mode::testing;
+    use crate::model::{Dir, ModeKind};
+    use crate::room::RoomRules;
+
+    fn queued(room: &crate::room::Room) -> Vec<Dir> {
+        room.players["a"].pending.iter().copied().collect()
+    }
+
+    #[test]
+    fn fast_turns_check_against_the_last_queued_direction() {
+        // heading right: up then left is a legal U-turn over two ticks...
+        let mut room = testing::room(ModeKind::Classic, &["a"]);
+        assert!(room.queue_input("a", Dir::Up));
+        assert!(room.queue_input("a", Dir::Left));
+        assert_eq!(queued(&room), [Dir::Up, Dir::Left]);
+
+        // ...but up, down would fold back into the neck
+        let mut room = testing::room(ModeKind::Classic, &["a"]);
+        room.queue_input("a", Dir::Up);
+        room.queue_input("a", Dir::Down);
+        assert_eq!(queued(&room), [Dir::Up]);
+
+        // and so would up, left, right once the left is queued
+        let mut room = testing::room(ModeKind::Classic, &["a"]);
+        room.queue_input("a", Dir::Up);
+        room.queue_input("a", Dir::Left);
+        room.queue_input("a", Dir::Right);
+        assert_eq!(queued(&room), [Dir::Up, Dir::Left]);
+    }
+
+    #[test]
+    fn duplicates_and_plain_reversals_are_dropped() {
+        let mut room = testing::room(ModeKind::Classic, &["a"]);
+        assert!(room.queue_input("a", Dir::Right));
+        assert!(room.queue_input("a", Dir::Left));
+        assert!(queued(&room).is_empty());
+
+        room.queue_input("a", Dir::Down);
+        room.queue_input("a", Dir::Down);
+        assert_eq!(queued(&room), [Dir::Down]);
+    }
+
+    #[test]
+    fn queue_is_capped_and_drains_one_turn_per_tick() {
+        let mut room = testing::room(ModeKind::Classic, &["a"]);
+        assert!(room.queue_input("a", Dir::Up));
+        assert!(room.queue_input("a", Dir::Left));
+        assert!(!room.queue_input("a", Dir::Down));
+        assert_eq!(queued(&room), [Dir::Up, Dir::Left]);
+
+        room.step();
+        assert_eq!(room.players["a"].dir, Dir::Up);
+        assert!(room.queue_input("a", Dir::Down));
+        assert_eq!(queued(&room), [Dir::Left, Dir::Down]);
+
+        room.input_queue_depth = 3;
+        assert!(room.queue_input("a", Dir::Right));
+        assert_eq!(queued(&room).len(), 3);
+    }
+
+    #[test]
+    fn bots_stop_at_the_snake_cap() {
+        let mut room = testing::room(ModeKind::Classic, &["a"]);
+        room.max_snakes = 3;
+        assert!(room.add_bot(BotTier::Greedy).is_ok());
+        assert!(room.add_bot(BotTier::Greedy).is_ok());
+        assert_eq!(
+            room.add_bot(BotTier::Greedy),
+            Err(ProtocolError::RoomFull { max: 3 })
+        );
+
+        // filling up to more than the cap stops at the cap too
+        room.set_rules(RoomRules {
+            fill_bots: 200,
+            ..Default::default()
+        });
+        assert_eq!(room.players.len(), 3);
+    }
+}
@@ -0,0 +1,61 @@
+//! Round-trip time for one connection.
+//!
+//! The server pings each client at the WebSocket level and feeds the
+//! replies in here. Smoothing follows TCP's retransmit timer (RFC 6298):
+//! the estimate moves an eighth of the way toward each sample and the
+//! jitter a quarter of the way toward the sample's deviation from it.
+
+use std::time::Duration;
+
+/// How often each connection is pinged.
+pub const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default)]
+pub struct RttEstimator {
+    /// Smoothed RTT and mean deviation, in milliseconds; `None` until the
+    /// first sample.
+    smoothed: Option<(f64, f64)>,
+}
+
+impl RttEstimator {
+    pub fn sample(&mut self, rtt: Duration) {
+        let r = rtt.as_secs_f64() * 1000.0;
+        self.smoothed = Some(match self.smoothed {
+            None => (r, r / 2.0),
+            Some((srtt, var)) => (
+                srtt + (r - srtt) / 8.0,
+                var + ((srtt - r).abs() - var) / 4.0,
+            ),
+        });
+    }
+
+    pub fn rtt_ms(&self) -> Option<u32> {
+        self.smoothed.map(|(srtt, _)| srtt.round() as u32)
+    }
+
+    pub fn jitter_ms(&self) -> Option<u32> {
+        self.smoothed.map(|(_, var)| var.round() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smooths_out_spikes_and_tracks_jitter() {
+        let ms = Duration::from_millis;
+        let mut e = RttEstimator::default();
+        assert_eq!(e.rtt_ms(), None);
+        e.sample(ms(40));
+        assert_eq!((e.rtt_ms(), e.jitter_ms()), (Some(40), Some(20)));
+        // one spike moves the estimate an eighth of the way
+        e.sample(ms(200));
+        assert_eq!((e.rtt_ms(), e.jitter_ms()), (Some(60), Some(55)));
+        // a steady link settles back down
+        for _ in 0..50 {
+            e.sample(ms(40));
+        }
+        assert_eq!((e.rtt_ms(), e.jitter_ms()), (Some(40), Some(0)));
+    }
+}
@@ -0,0 +1,429 @@
+//! Headless bot tournaments.
+//!
+//! Every match is a seeded `Room` stepped with `advance`, so a tournament with
+//! the same settings always produces the same table. Matches within a round
+//! run in parallel on plain threads.
+
+use crate::bot::{self, BotTier, BotView};
+use crate::mode;
+use crate::model::Dir;
+use crate::room::{Room, RoomRules};
+use rand::rngs::StdRng;
+use serde::Serialize;
+use std::fmt::{self, Write as _};
+use std::sync::Arc;
+
+/// Picks a move each tick from the same view the built-in bots get.
+pub type Strategy = dyn Fn(&BotView<'_>, &mut StdRng) -> Dir + Send + Sync;
+
+/// A strategy that can be entered into a tournament.
+#[derive(Clone, Serialize)]
+pub struct Entrant {
+    pub name: String,
+    #[serde(skip)]
+    pub strategy: Arc<Strategy>,
+}
+
+impl Entrant {
+    pub fn new(
+        name: impl Into<String>,
+        strategy: impl Fn(&BotView<'_>, &mut StdRng) -> Dir + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            strategy: Arc::new(strategy),
+        }
+    }
+}
+
+impl fmt::Debug for Entrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entrant")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The built-in tiers, by wire name. Push your own `Entrant`s onto this to
+/// pit an experimental strategy against them.
+pub fn registry() -> Vec<Entrant> {
+    BotTier::ALL
+        .iter()
+        .map(|&tier| {
+            let name = serde_json::to_value(tier)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default();
+            Entrant::new(name, move |view: &BotView<'_>, rng: &mut StdRng| {
+                bot::choose(tier, view, rng)
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Every entrant meets every other entrant.
+    RoundRobin,
+    /// This many rounds, pairing entrants on equal points.
+    Swiss { rounds: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentConfig {
+    pub format: Format,
+    pub rules: RoomRules,
+    pub grid: (i32, i32),
+    /// Matches played per pairing, each with its own seed.
+    pub games: u32,
+    /// A match still running after this many ticks is scored as it stands.
+    pub max_ticks: u64,
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            format: Format::RoundRobin,
+            rules: RoomRules::default(),
+            grid: (22, 22),
+            games: 10,
+            max_ticks: 1000,
+            seed: 0,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// One finished match between two entrants (indices into the entrant list).
+#[derive(Debug, Clone)]
+struct MatchResult {
+    pair: (usize, usize),
+    scores: (u32, u32),
+    survival: (u64, u64),
+    /// 0 or 1 for `pair.0` / `pair.1`; `None` for a draw.
+    winner: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Row {
+    pub name: String,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Wins count 1, draws ½, byes 1.
+    pub points: f32,
+    pub win_rate: f32,
+    pub avg_score: f32,
+    pub avg_survival_ticks: f32,
+    #[serde(skip)]
+    total_score: u64,
+    #[serde(skip)]
+    total_survival: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Standings {
+    pub format: String,
+    pub mode: crate::model::ModeKind,
+    pub games_per_pairing: u32,
+    pub seed: u64,
+    pub matches: usize,
+    pub rows: Vec<Row>,
+}
+
+pub fn run(entrants: &[Entrant], cfg: &TournamentConfig) -> Standings {
+    let mut rows: Vec<Row> = entrants
+        .iter()
+        .map(|e| Row {
+            name: e.name.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut results: Vec<MatchResult> = vec![];
+
+    match cfg.format {
+        Format::RoundRobin => {
+            let mut pairs = vec![];
+            for a in 0..entrants.len() {
+                for b in a + 1..entrants.len() {
+                    pairs.push((a, b));
+                }
+            }
+            results.extend(play_pairs(entrants, &pairs, cfg, 0));
+        }
+        Format::Swiss { rounds } => {
+            let mut met: Vec<(usize, usize)> = vec![];
+            for round in 0..rounds {
+                let (pairs, bye) = swiss_pairs(&rows, &met);
+                if let Some(i) = bye {
+                    rows[i].points += 1.0;
+                }
+                let round_results = play_pairs(entrants, &pairs, cfg, round as u64);
+                for r in &round_results {
+                    tally(&mut rows, r);
+                }
+                met.extend(pairs);
+                results.extend(round_results);
+            }
+        }
+    }
+    if cfg.format == Format::RoundRobin {
+        for r in &results {
+            tally(&mut rows, r);
+        }
+    }
+
+    for row in &mut rows {
+        let n = row.played.max(1) as f32;
+        row.win_rate = row.wins as f32 / n;
+        row.avg_score = row.total_score as f32 / n;
+        row.avg_survival_ticks = row.total_survival as f32 / n;
+    }
+    rows.sort_by(|a, b| {
+        b.points
+            .total_cmp(&a.points)
+            .then(b.win_rate.total_cmp(&a.win_rate))
+            .then(b.avg_score.total_cmp(&a.avg_score))
+    });
+
+    Standings {
+        format: match cfg.format {
+            Format::RoundRobin => "round-robin".into(),
+            Format::Swiss { rounds } => format!("swiss ({rounds} rounds)"),
+        },
+        mode: cfg.rules.mode,
+        games_per_pairing: cfg.games,
+        seed: cfg.seed,
+        matches: results.len(),
+        rows,
+    }
+}
+
+fn tally(rows: &mut [Row], r: &MatchResult) {
+    let (a, b) = r.pair;
+    for (i, score, survival) in [(a, r.scores.0, r.survival.0), (b, r.scores.1, r.survival.1)] {
+        rows[i].played += 1;
+        rows[i].total_score += score as u64;
+        rows[i].total_survival += survival;
+    }
+    match r.winner {
+        Some(w) => {
+            let (win, lose) = if w == 0 { (a, b) } else { (b, a) };
+            rows[win].wins += 1;
+            rows[win].points += 1.0;
+            rows[lose].losses += 1;
+        }
+        None => {
+            for i in [a, b] {
+                rows[i].draws += 1;
+                rows[i].points += 0.5;
+            }
+        }
+    }
+}
+
+/// Pair entrants with similar points who haven't met yet; the odd one out gets a bye.
+fn swiss_pairs(rows: &[Row], met: &[(usize, usize)]) -> (Vec<(usize, usize)>, Option<usize>) {
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|a, b| rows[*b].points.total_cmp(&rows[*a].points).then(a.cmp(b)));
+    let have_met = |a: usize, b: usize| met.contains(&(a, b)) || met.contains(&(b, a));
+
+    let mut pairs = vec![];
+    while !order.is_empty() {
+        let a = order.remove(0);
+        if order.is_empty() {
+            return (pairs, Some(a));
+        }
+        let j = order.iter().position(|b| !have_met(a, *b)).unwrap_or(0);
+        pairs.push((a, order.remove(j)));
+    }
+    (pairs, None)
+}
+
+/// Play `cfg.games` matches for each pair, spread over `cfg.threads` threads.
+fn play_pairs(
+    entrants: &[Entrant],
+    pairs: &[(usize, usize)],
+    cfg: &TournamentConfig,
+    round: u64,
+) -> Vec<MatchResult> {
+    let jobs: Vec<((usize, usize), u64)> = pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(p, pair)| {
+            (0..cfg.games as u64).map(move |g| {
+                // distinct, reproducible seed per (round, pairing, game)
+                let seed = cfg
+                    .seed
+                    .wrapping_add(round.wrapping_mul(1_000_003))
+                    .wrapping_add((p as u64).wrapping_mul(10_007))
+                    .wrapping_add(g);
+                (*pair, seed)
+            })
+        })
+        .collect();
+    if jobs.is_empty() {
+        return vec![];
+    }
+
+    let chunk = jobs.len().div_ceil(cfg.threads.max(1));
+    std::thread::scope(|s| {
+        let handles: Vec<_> = jobs
+            .chunks(chunk)
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(pair, seed)| play_match(entrants, *pair, *seed, cfg))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            // a strategy that panicked would leave its matches out of the table
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+fn play_match(
+    entrants: &[Entrant],
+    pair: (usize, usize),
+    seed: u64,
+    cfg: &TournamentConfig,
+) -> MatchResult {
+    let mut room = Room::new("tournament", cfg.grid.0, cfg.grid.1, 10, cfg.rules);
+    room.reseed(seed);
+    let sides = [&entrants[pair.0], &entrants[pair.1]];
+    let ids = ["p0".to_string(), "p1".to_string()];
+    for (id, e) in ids.iter().zip(sides) {
+        room.insert_player(id.clone(), e.name.clone(), None, None);
+    }
+    room.reset_round();
+    room.start();
+
+    let mut survival = [0u64; 2];
+    while !room.round_over && room.round_ticks < cfg.max_ticks {
+        for (id, e) in ids.iter().zip(sides) {
+            room.drive_with(id, &*e.strategy);
+        }
+        room.advance();
+        for (i, id) in ids.iter().enumerate() {
+            if room.players.get(id).is_some_and(|p| p.alive) {
+                survival[i] += 1;
+            }
+        }
+    }
+
+    let score = |id: &str| room.players.get(id).map_or(0, |p| p.score);
+    let winner =
+        mode::sole_winner(&room.standings()).and_then(|w| ids.iter().position(|id| *id == w));
+    MatchResult {
+        pair,
+        scores: (score(&ids[0]), score(&ids[1])),
+        survival: (survival[0], survival[1]),
+        winner,
+    }
+}
+
+impl Standings {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "## Bot tournament: {} / {:?}\n\n{} matches, {} per pairing, seed {}\n",
+            self.format, self.mode, self.matches, self.games_per_pairing, self.seed
+        );
+        out.push_str("| # | Bot | Played | W | D | L | Points | Win rate | Avg score | Avg survival (ticks) |\n");
+        out.push_str("|---|-----|-------:|--:|--:|--:|-------:|---------:|----------:|---------------------:|\n");
+        for (i, r) in self.rows.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} | {:.1} | {:.1}% | {:.2} | {:.1} |",
+                i + 1,
+                r.name,
+                r.played,
+                r.wins,
+                r.draws,
+                r.losses,
+                r.points,
+                r.win_rate * 100.0,
+                r.avg_score,
+                r.avg_survival_ticks
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModeKind;
+
+    fn quick(format: Format) -> TournamentConfig {
+        TournamentConfig {
+            format,
+            rules: RoomRules {
+                mode: ModeKind::BattleRoyale,
+                ..Default::default()
+            },
+            games: 2,
+            max_ticks: 200,
+            seed: 9,
+            threads: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_robin_plays_every_pair() {
+        let table = run(&registry(), &quick(Format::RoundRobin));
+        assert_eq!(table.matches, 3 * 2);
+        assert!(table.rows.iter().all(|r| r.played == 4));
+        assert_eq!(
+            table.to_json(),
+            run(&registry(), &quick(Format::RoundRobin)).to_json()
+        );
+    }
+
+    #[test]
+    fn strategies_enter_without_a_bot_tier() {
+        let mut entrants = registry();
+        entrants.push(Entrant::new(
+            "wall-hugger",
+            |view: &BotView<'_>, _: &mut StdRng| view.dir,
+        ));
+        let table = run(&entrants, &quick(Format::RoundRobin));
+        let row = table.rows.iter().find(|r| r.name == "wall-hugger").unwrap();
+        assert_eq!(row.played, 3 * 2);
+        assert!(row.wins < row.played);
+    }
+
+    #[test]
+    #[should_panic(expected = "strategy blew up")]
+    fn a_panicking_strategy_is_not_left_out_quietly() {
+        let mut entrants = registry();
+        entrants.push(Entrant::new("broken", |_: &BotView<'_>, _: &mut StdRng| {
+            panic!("strategy blew up")
+        }));
+        run(&entrants, &quick(Format::RoundRobin));
+    }
+
+    #[test]
+    fn swiss_gives_the_odd_entrant_a_bye() {
+        let table = run(&registry(), &quick(Format::Swiss { rounds: 2 }));
+        assert_eq!(table.matches, 2 * 2);
+        let points: f32 = table.rows.iter().map(|r| r.points).sum();
+        // 4 decided or drawn matches worth 1 point each, plus one bye per round
+        assert_eq!(points, 4.0 + 2.0);
+    }
+}
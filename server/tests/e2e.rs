@@ -0,0 +1,509 @@
+mod support;
+
+use snake_server::bot::BotTier;
+use snake_server::clock::ManualClock;
+use snake_server::config::Limits;
+use snake_server::model::{Dir, DisconnectReason, ErrorCode, ModeKind, C2S, PROTOCOL_VERSION, S2C};
+use snake_server::ServerBuilder;
+use std::{sync::Arc, time::Duration};
+use support::{player, seq, BotPilot, TestServer};
+
+#[tokio::test]
+async fn two_players_join_and_start() {
+    let server = TestServer::start().await;
+    let mut a = server.connect("duel", "Ann").await;
+    let first = a.recv().await;
+    assert_eq!(player(&first, &a.id).name, "Ann");
+
+    let mut b = server.connect("duel", "Bob").await;
+    let joined = b.recv().await;
+    assert!(matches!(&joined, S2C::State { players, started: false, .. } if players.len() == 2));
+
+    a.send(C2S::Start).await;
+    a.sync().await;
+    server.tick();
+    let state = b.state_after(0).await;
+    assert!(matches!(
+        state,
+        S2C::State {
+            started: true,
+            seq: 1,
+            ..
+        }
+    ));
+    assert!(player(&state, &a.id).alive && player(&state, &b.id).alive);
+}
+
+#[tokio::test]
+async fn steering_into_the_wall_costs_a_life() {
+    let server = TestServer::start().await;
+    let mut a = server.connect("wall", "Ann").await;
+    let mut b = server.connect("wall", "Bob").await;
+    a.send(C2S::Start).await;
+    a.send(C2S::Input { dir: Dir::Up }).await;
+    a.sync().await;
+
+    // Ann starts mid-board heading up; Bob dodges with the survivor bot so
+    // that Ann's death isn't masked by a shared respawn
+    let mut last_seq = 0;
+    let mut pilot = BotPilot::new(BotTier::Survivor);
+    for _ in 0..30 {
+        server.tick();
+        let state = a.state_after(last_seq).await;
+        assert_eq!(seq(&state), last_seq + 1, "one State per tick");
+        last_seq = seq(&state);
+
+        let ann = player(&state, &a.id);
+        if !ann.alive {
+            assert_eq!(ann.lives, 2);
+            let bob = player(&state, &b.id);
+            assert!(bob.alive && bob.lives == 3);
+            return;
+        }
+        assert_eq!(ann.lives, 3);
+        b.send(C2S::Input {
+            dir: pilot.steer(&state, &b.id),
+        })
+        .await;
+        b.sync().await;
+    }
+    panic!("Ann never hit the wall");
+}
+
+#[tokio::test]
+async fn server_enforces_room_limits() {
+    let server = TestServer::start_with(ServerBuilder::new().limits(Limits {
+        max_players_per_room: 1,
+        ..Default::default()
+    }))
+    .await;
+    let _a = server.connect("solo", "Ann").await;
+    assert_eq!(
+        server.refused("room=solo&name=Bob").await,
+        DisconnectReason::RoomFull
+    );
+}
+
+#[tokio::test]
+async fn ticker_runs_on_the_injected_clock() {
+    let clock = Arc::new(ManualClock::new());
+    let server = TestServer::start_with(ServerBuilder::new().clock(clock.clone())).await;
+    let mut a = server.connect("clock", "Ann").await;
+    a.send(C2S::Start).await;
+    a.sync().await;
+
+    // half a tick: nothing happens, however long the test takes
+    clock.advance(Duration::from_millis(50));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    a.sync().await;
+    assert!(a
+        .drain()
+        .iter()
+        .all(|m| !matches!(m, S2C::State { seq, .. } if *seq > 0)));
+
+    clock.advance(Duration::from_millis(50));
+    assert_eq!(seq(&a.state_after(0).await), 1);
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(seq(&a.state_after(1).await), 2);
+}
+
+#[tokio::test]
+async fn metrics_count_rooms_messages_and_ticks() {
+    let server = TestServer::start().await;
+    let mut a = server.connect("metrics", "Ann").await;
+    a.send(C2S::Start).await;
+    a.sync().await;
+    server.tick();
+    a.state_after(0).await;
+
+    let (status, body) = server.get("/metrics").await;
+    assert!(status.contains("200"), "{status}");
+    assert!(body.contains("snake_rooms_active 1\n"));
+    assert!(body.contains("snake_room_connections{room=\"metrics\"} 1\n"));
+    assert!(body.contains("snake_room_tick_duration_seconds_count{room=\"metrics\"} 1\n"));
+    assert!(body.contains("snake_messages_received_total{type=\"start\"} 1\n"));
+    assert!(body.contains("snake_messages_received_total{type=\"ping\"} 1\n"));
+    assert!(body.contains("snake_connections_opened_total 1\n"));
+    assert!(!body.contains("snake_snapshot_bytes_sent_total 0\n"));
+    let depth = format!(
+        "snake_outbound_queue_depth{{room=\"metrics\",player=\"{}\"}} 0\n",
+        a.id
+    );
+    assert!(body.contains(&depth), "{body}");
+}
+
+#[tokio::test]
+async fn server_measures_each_players_ping() {
+    let server = TestServer::start_with(ServerBuilder::new().manual_ticks()).await;
+    let mut a = server.connect("ping", "Ann").await;
+    // the server pings as soon as we connect; the answer goes out with our
+    // next message, ahead of it
+    a.send(C2S::Start).await;
+    a.sync().await;
+    server.tick();
+    let state = a.state_after(0).await;
+    let ping = player(&state, &a.id).ping_ms.expect("ping measured");
+    assert!(ping < 1000, "{ping}");
+
+    let (_, body) = server.get("/metrics").await;
+    let gauge = format!(
+        "snake_player_rtt_seconds{{room=\"ping\",player=\"{}\"}} ",
+        a.id
+    );
+    assert!(body.contains(&gauge), "{body}");
+}
+
+#[tokio::test]
+async fn rooms_record_replays_when_asked() {
+    let dir = std::env::temp_dir().join(format!("snake-e2e-replays-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let server = TestServer::start_with(
+        ServerBuilder::new()
+            .manual_ticks()
+            .replay_dir(Some(dir.clone())),
+    )
+    .await;
+    let mut a = server.connect("rec", "Ann").await;
+    a.send(C2S::Start).await;
+    a.sync().await;
+    server.tick();
+    server.tick();
+    a.state_after(1).await;
+
+    // flushed on a timer, while the room is still open
+    let mut text = String::new();
+    for _ in 0..100 {
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+        if let [file] = files.as_slice() {
+            assert!(file.file_name().to_string_lossy().starts_with("rec-"));
+            text = std::fs::read_to_string(file.path()).unwrap();
+            if !text.is_empty() {
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let seqs: Vec<u64> = text
+        .lines()
+        .map(|l| seq(&serde_json::from_str(l).expect("valid S2C")))
+        .collect();
+    assert_eq!(seqs, [1, 2]);
+    drop(a);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn drain_refuses_joins_and_stops_after_the_round() {
+    let server = TestServer::start().await;
+    let mut a = server
+        .connect_query("room=drain&name=Ann&mode=royale")
+        .await;
+    a.send(C2S::Start).await;
+    a.sync().await;
+    assert!(server.get("/readyz").await.0.contains("200"));
+
+    server.handle.drain();
+    let (status, _) = server.get("/readyz").await;
+    assert!(status.contains("503"), "{status}");
+    assert!(server.get("/healthz").await.0.contains("200"));
+
+    assert_eq!(
+        server.refused("room=drain&name=Bob").await,
+        DisconnectReason::ServerShutdown
+    );
+
+    // the round in progress keeps the server up until Ann hits the wall
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(server.get("/healthz").await.0.contains("200"));
+    a.send(C2S::Input { dir: Dir::Up }).await;
+    a.sync().await;
+    for _ in 0..30 {
+        server.tick();
+    }
+    tokio::time::timeout(Duration::from_secs(2), server.handle.wait())
+        .await
+        .expect("server should stop once the round is over")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn drain_does_not_wait_on_endless_modes() {
+    let server = TestServer::start_with(ServerBuilder::new().manual_ticks()).await;
+    let mut a = server.connect("classic", "Ann").await;
+    let mut b = server.connect_query("room=teams&name=Bob&mode=team").await;
+    for c in [&mut a, &mut b] {
+        c.send(C2S::Start).await;
+        c.sync().await;
+    }
+    server.tick();
+
+    server.handle.drain();
+    tokio::time::timeout(Duration::from_secs(2), server.handle.wait())
+        .await
+        .expect("classic and team rooms have no round to wait for")
+        .unwrap();
+    assert_eq!(
+        a.disconnect_reason().await,
+        DisconnectReason::ServerShutdown
+    );
+}
+
+#[tokio::test]
+async fn clients_are_told_why_they_are_dropped() {
+    let server = TestServer::start_with(ServerBuilder::new().manual_ticks().limits(Limits {
+        idle_timeout_secs: 1,
+        ..Default::default()
+    }))
+    .await;
+    assert_eq!(
+        server.refused("room=v&name=Old&v=999").await,
+        DisconnectReason::VersionMismatch
+    );
+    let mut current = server
+        .connect_query(&format!("room=v&name=New&v={PROTOCOL_VERSION}"))
+        .await;
+    current.sync().await;
+
+    // Ann stays silent past the idle timeout; Bob keeps pinging
+    let mut a = server.connect("idle", "Ann").await;
+    let mut b = server.connect("idle", "Bob").await;
+    for _ in 0..3 {
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        b.sync().await;
+    }
+    assert_eq!(a.disconnect_reason().await, DisconnectReason::IdleTimeout);
+    assert!(a.closed().await);
+
+    server.handle.stop().await.unwrap();
+    assert_eq!(
+        b.disconnect_reason().await,
+        DisconnectReason::ServerShutdown
+    );
+    assert!(b.closed().await);
+}
+
+#[tokio::test]
+async fn admin_api_needs_the_token_and_drives_rooms() {
+    let closed = TestServer::start().await;
+    assert!(closed.get("/admin/rooms").await.0.contains("404"));
+
+    const TOKEN: &str = "s3cret";
+    let server = TestServer::start_with(
+        ServerBuilder::new()
+            .manual_ticks()
+            .admin_token(Some(TOKEN.into())),
+    )
+    .await;
+    let admin = |method: &'static str, path: &'static str, body: &'static str| {
+        server.request(method, path, Some(TOKEN), body)
+    };
+    assert!(server.get("/admin/rooms").await.0.contains("401"));
+    assert!(server
+        .request("GET", "/admin/rooms", Some("guess"), "")
+        .await
+        .0
+        .contains("401"));
+
+    let mut a = server.connect("ops", "Ann").await;
+    let mut b = server.connect("ops", "Bob").await;
+    let (status, body) = admin("GET", "/admin/rooms", "").await;
+    assert!(status.contains("200"), "{status}");
+    assert!(
+        body.contains("\"Ann\"") && body.contains("127.0.0.1"),
+        "{body}"
+    );
+    assert!(admin("GET", "/admin/rooms/nope", "")
+        .await
+        .0
+        .contains("404"));
+
+    let (_, body) = admin(
+        "POST",
+        "/admin/broadcast",
+        r#"{"message":"hi all","room":"ops"}"#,
+    )
+    .await;
+    assert_eq!(body, r#"{"reached":2}"#);
+    loop {
+        if let S2C::Announcement { text } = a.recv().await {
+            assert_eq!(text, "hi all");
+            break;
+        }
+    }
+
+    // paused rooms don't tick
+    a.send(C2S::Start).await;
+    a.sync().await;
+    assert!(admin("POST", "/admin/rooms/ops/pause", "")
+        .await
+        .0
+        .contains("204"));
+    server.tick();
+    a.sync().await;
+    let got = a.drain();
+    assert!(!got
+        .iter()
+        .any(|m| matches!(m, S2C::State { seq, .. } if *seq > 0)));
+    admin("POST", "/admin/rooms/ops/resume", "").await;
+    server.tick();
+    a.state_after(0).await;
+
+    assert!(
+        admin("PUT", "/admin/rooms/ops/rules", r#"{"mode":"royale"}"#)
+            .await
+            .0
+            .contains("204")
+    );
+    loop {
+        if let S2C::Hello { mode, .. } = a.recv().await {
+            assert_eq!(mode, ModeKind::BattleRoyale);
+            break;
+        }
+    }
+
+    let kick = format!("/admin/rooms/ops/players/{}", b.id);
+    server.request("DELETE", &kick, Some(TOKEN), "").await;
+    assert_eq!(b.disconnect_reason().await, DisconnectReason::Kicked);
+    assert!(b.closed().await);
+
+    assert!(admin("DELETE", "/admin/rooms/ops", "")
+        .await
+        .0
+        .contains("204"));
+    assert_eq!(a.disconnect_reason().await, DisconnectReason::RoomClosed);
+    assert!(a.closed().await);
+    assert_eq!(admin("GET", "/admin/rooms", "").await.1, "[]");
+}
+
+#[tokio::test]
+async fn bots_are_capped_per_room() {
+    let server = TestServer::start_with(ServerBuilder::new().manual_ticks().limits(Limits {
+        max_snakes_per_room: 4,
+        ..Default::default()
+    }))
+    .await;
+    let mut a = server.connect_query("room=crowd&name=Ann&bots=255").await;
+    let state = a.recv().await;
+    assert!(matches!(&state, S2C::State { players, .. } if players.len() == 4));
+
+    a.send(C2S::AddBot {
+        tier: BotTier::Greedy,
+    })
+    .await;
+    a.sync().await;
+    assert!(a.drain().iter().any(|m| matches!(
+        m,
+        S2C::Error {
+            code: ErrorCode::RoomFull,
+            ..
+        }
+    )));
+}
+
+#[tokio::test]
+async fn refused_requests_get_an_error_reply() {
+    let server = TestServer::start_with(ServerBuilder::new().manual_ticks()).await;
+    let mut a = server.connect("errs", "Ann").await;
+    let mut b = server.connect("errs", "Bob").await;
+
+    let errors = |c: &mut support::TestClient| -> Vec<(ErrorCode, Option<String>)> {
+        c.drain()
+            .into_iter()
+            .filter_map(|m| match m {
+                S2C::Error {
+                    code, reference, ..
+                } => Some((code, reference)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    b.send_raw("{not json".into()).await;
+    b.send_raw(r#"{"type":"teleport"}"#.into()).await;
+    b.send(C2S::AddBot {
+        tier: BotTier::RandomSafe,
+    })
+    .await;
+    b.sync().await;
+    assert_eq!(
+        errors(&mut b),
+        [
+            (ErrorCode::BadMessage, None),
+            (ErrorCode::BadMessage, None),
+            (ErrorCode::NotOwner, Some("add_bot".into())),
+        ]
+    );
+
+    a.send(C2S::RemoveBot {
+        id: "nobody".into(),
+    })
+    .await;
+    a.sync().await;
+    assert_eq!(
+        errors(&mut a),
+        [(ErrorCode::NoSuchBot, Some("remove_bot".into()))]
+    );
+
+    // mistakes are answered, not held against the client
+    b.sync().await;
+    let (_, text) = server.get("/metrics").await;
+    assert!(text.contains("snake_protocol_errors_total{code=\"bad_message\"} 2\n"));
+    assert!(text.contains("snake_parse_failures_total 2\n"));
+}
+
+#[tokio::test]
+async fn floods_are_dropped_and_repeat_offenders_cut_off() {
+    let server = TestServer::start_with(ServerBuilder::new().manual_ticks().limits(Limits {
+        max_frame_bytes: 256,
+        max_violations: 3,
+        ..Default::default()
+    }))
+    .await;
+    let mut a = server.connect("flood", "Ann").await;
+    let mut b = server.connect("flood", "Bob").await;
+
+    // one oversized frame is dropped, not fatal
+    b.send_raw(format!(
+        r#"{{"type":"ping","t":1,"pad":"{}"}}"#,
+        "x".repeat(300)
+    ))
+    .await;
+    b.sync().await;
+
+    for _ in 0..20 {
+        a.send(C2S::Start).await;
+    }
+    assert_eq!(a.disconnect_reason().await, DisconnectReason::RateLimited);
+    assert!(a.closed().await);
+    b.sync().await;
+
+    let (_, text) = server.get("/metrics").await;
+    assert!(
+        text.contains("snake_rate_limited_total{type=\"start\"} 4\n"),
+        "{text}"
+    );
+    assert!(text.contains("snake_oversized_frames_total 1\n"));
+    assert!(text.contains("snake_flood_disconnects_total 1\n"));
+
+    // a few bad frames are answered; a stream of them is a flood like any other
+    let mut c = server.connect("flood", "Cid").await;
+    for _ in 0..20 {
+        c.send_raw("not json".into()).await;
+    }
+    let mut answered = 0;
+    loop {
+        match c.recv().await {
+            S2C::Error { code, .. } => {
+                assert_eq!(code, ErrorCode::BadMessage);
+                answered += 1;
+            }
+            S2C::Disconnect { reason } => {
+                assert_eq!(reason, DisconnectReason::RateLimited);
+                break;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(answered, 5);
+    assert!(c.closed().await);
+}
@@ -0,0 +1,273 @@
+//! Shared helpers for end-to-end tests: a server on an ephemeral port with
+//! manual ticks, and scripted WebSocket clients that record what they receive.
+
+#![allow(dead_code)] // not every test binary uses every helper
+
+use futures_util::{SinkExt, StreamExt};
+use rand::{rngs::StdRng, SeedableRng};
+use snake_server::bot::{self, BotTier, BotView};
+use snake_server::model::{Dir, DisconnectReason, PlayerSnapshot, C2S, S2C};
+use snake_server::{ServerBuilder, ServerHandle};
+use std::collections::VecDeque;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct TestServer {
+    pub handle: ServerHandle,
+}
+
+impl TestServer {
+    /// Start on `127.0.0.1:0` with manual ticks: rooms only advance on `tick`.
+    pub async fn start() -> Self {
+        Self::start_with(ServerBuilder::new().manual_ticks()).await
+    }
+
+    /// Start a customised server on `127.0.0.1:0`.
+    pub async fn start_with(builder: ServerBuilder) -> Self {
+        let handle = builder
+            .bind(Ipv4Addr::LOCALHOST.into())
+            .port(0)
+            .start()
+            .await
+            .expect("server should start");
+        Self { handle }
+    }
+
+    /// Join `room` as `name` and wait for the server's `Hello`.
+    pub async fn connect(&self, room: &str, name: &str) -> TestClient {
+        self.connect_query(&format!("room={room}&name={name}"))
+            .await
+    }
+
+    /// Connect with a raw query string, e.g. to pass room rules.
+    pub async fn connect_query(&self, query: &str) -> TestClient {
+        let url = format!("ws://{}/ws?{query}", self.handle.local_addr());
+        let (ws, _) = connect_async(url).await.expect("websocket should connect");
+        let mut client = TestClient {
+            ws,
+            id: String::new(),
+            inbox: VecDeque::new(),
+            next_ping: 1,
+        };
+        match client.recv().await {
+            S2C::Hello { player_id, .. } => client.id = player_id,
+            other => panic!("expected Hello first, got {other:?}"),
+        }
+        client
+    }
+
+    /// Connect expecting to be turned away; returns the reason given.
+    pub async fn refused(&self, query: &str) -> DisconnectReason {
+        let url = format!("ws://{}/ws?{query}", self.handle.local_addr());
+        let (ws, _) = connect_async(url).await.expect("websocket should connect");
+        let mut client = TestClient {
+            ws,
+            id: String::new(),
+            inbox: VecDeque::new(),
+            next_ping: 1,
+        };
+        let reason = client.disconnect_reason().await;
+        assert!(client.closed().await, "refused connection should close");
+        reason
+    }
+
+    /// Advance every started room by exactly one tick.
+    pub fn tick(&self) {
+        self.handle.tick();
+    }
+
+    /// `GET path` over a plain socket; returns the status line and the body.
+    pub async fn get(&self, path: &str) -> (String, String) {
+        self.request("GET", path, None, "").await
+    }
+
+    /// Any HTTP request over a plain socket, with an optional bearer token and
+    /// JSON body; returns the status line and the body.
+    pub async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        body: &str,
+    ) -> (String, String) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let mut stream = TcpStream::connect(self.handle.local_addr()).await.unwrap();
+        let mut request =
+            format!("{method} {path} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n");
+        if let Some(token) = token {
+            request += &format!("Authorization: Bearer {token}\r\n");
+        }
+        if !body.is_empty() {
+            request += "Content-Type: application/json\r\n";
+        }
+        request += &format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        tokio::time::timeout(RECV_TIMEOUT, stream.read_to_string(&mut response))
+            .await
+            .expect("timed out reading response")
+            .unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+        let status = head.lines().next().unwrap_or_default().to_string();
+        (status, body.to_string())
+    }
+}
+
+pub struct TestClient {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    pub id: String,
+    /// Received but not yet consumed by the test.
+    inbox: VecDeque<S2C>,
+    next_ping: u64,
+}
+
+impl TestClient {
+    pub async fn send(&mut self, msg: C2S) {
+        let text = serde_json::to_string(&msg).unwrap();
+        self.ws.send(Message::Text(text)).await.expect("send");
+    }
+
+    /// Send any text frame, valid or not.
+    pub async fn send_raw(&mut self, text: String) {
+        self.ws.send(Message::Text(text)).await.expect("send");
+    }
+
+    async fn read(&mut self) -> Option<S2C> {
+        loop {
+            let msg = tokio::time::timeout(RECV_TIMEOUT, self.ws.next())
+                .await
+                .expect("timed out waiting for the server")?;
+            match msg.ok()? {
+                Message::Text(txt) => return Some(serde_json::from_str(&txt).expect("valid S2C")),
+                Message::Close(_) => return None,
+                _ => continue,
+            }
+        }
+    }
+
+    /// Next message in arrival order; panics on timeout or close.
+    pub async fn recv(&mut self) -> S2C {
+        if let Some(msg) = self.inbox.pop_front() {
+            return msg;
+        }
+        self.read().await.expect("connection closed")
+    }
+
+    /// Wait until the server has handled everything sent so far, by a
+    /// ping/pong round trip. Messages received meanwhile stay queued.
+    pub async fn sync(&mut self) {
+        let t = self.next_ping;
+        self.next_ping += 1;
+        self.send(C2S::Ping { t }).await;
+        loop {
+            match self.read().await.expect("connection closed") {
+                S2C::Pong { t: got } if got == t => return,
+                other => self.inbox.push_back(other),
+            }
+        }
+    }
+
+    /// Skip ahead to the first `State` with `seq` greater than `after`.
+    pub async fn state_after(&mut self, after: u64) -> S2C {
+        loop {
+            let msg = self.recv().await;
+            if matches!(msg, S2C::State { seq, .. } if seq > after) {
+                return msg;
+            }
+        }
+    }
+
+    /// Everything received so far and not yet consumed.
+    pub fn drain(&mut self) -> Vec<S2C> {
+        self.inbox.drain(..).collect()
+    }
+
+    /// Skip ahead to the server's `Disconnect` and return its reason.
+    pub async fn disconnect_reason(&mut self) -> DisconnectReason {
+        loop {
+            if let S2C::Disconnect { reason } = self.recv().await {
+                return reason;
+            }
+        }
+    }
+
+    /// Wait for the server to close the connection.
+    pub async fn closed(&mut self) -> bool {
+        self.inbox.clear();
+        loop {
+            match tokio::time::timeout(RECV_TIMEOUT, self.ws.next()).await {
+                Ok(None | Some(Err(_)) | Some(Ok(Message::Close(_)))) => return true,
+                Ok(Some(Ok(_))) => continue,
+                Err(_) => return false,
+            }
+        }
+    }
+}
+
+/// Find a player in a `State` message.
+pub fn player<'a>(state: &'a S2C, id: &str) -> &'a PlayerSnapshot {
+    match state {
+        S2C::State { players, .. } => players
+            .iter()
+            .find(|p| p.id == id)
+            .unwrap_or_else(|| panic!("player {id} not in state")),
+        other => panic!("expected State, got {other:?}"),
+    }
+}
+
+pub fn seq(state: &S2C) -> u64 {
+    match state {
+        S2C::State { seq, .. } => *seq,
+        other => panic!("expected State, got {other:?}"),
+    }
+}
+
+/// Steers a scripted client with one of the built-in bot tiers.
+pub struct BotPilot {
+    tier: BotTier,
+    rng: StdRng,
+}
+
+impl BotPilot {
+    pub fn new(tier: BotTier) -> Self {
+        Self {
+            tier,
+            rng: StdRng::seed_from_u64(1),
+        }
+    }
+
+    pub fn steer(&mut self, state: &S2C, id: &str) -> Dir {
+        let S2C::State {
+            players,
+            food,
+            arena,
+            ..
+        } = state
+        else {
+            panic!("expected State, got {state:?}");
+        };
+        let me = player(state, id);
+        let dir = match me.body.as_slice() {
+            [h, n, ..] if h.1 < n.1 => Dir::Up,
+            [h, n, ..] if h.1 > n.1 => Dir::Down,
+            [h, n, ..] if h.0 < n.0 => Dir::Left,
+            _ => Dir::Right,
+        };
+        let view = BotView {
+            arena: *arena,
+            food: *food,
+            me: &me.body,
+            dir,
+            others: players
+                .iter()
+                .filter(|p| p.alive && p.id != id)
+                .map(|p| p.body.as_slice())
+                .collect(),
+        };
+        bot::choose(self.tier, &view, &mut self.rng)
+    }
+}